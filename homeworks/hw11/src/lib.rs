@@ -1,37 +1,48 @@
 use std::fmt::Display;
+use std::marker::PhantomData;
+use std::ptr::NonNull;
+
+/// A link to the next node, the node behind it is owned by the list.
+type Link<T> = Option<NonNull<Node<T>>>;
 
 struct Node<T> {
     value: T,
-    next: Option<Box<Node<T>>>,
+    next: Link<T>,
+}
+
+impl<T> Node<T> {
+    /// Moves the node to the heap, the list frees it with `Box::from_raw` once it is unlinked.
+    fn into_link(self) -> NonNull<Node<T>> {
+        // SAFETY: `Box::into_raw` never returns a null pointer.
+        unsafe { NonNull::new_unchecked(Box::into_raw(Box::new(self))) }
+    }
 }
 
+/// A singly linked list owning its nodes through raw pointers, so the tail can point
+/// into the same chain as the head and `push_back` takes constant time.
+///
+/// Every reference to a node is derived from these pointers while the list is borrowed,
+/// none of them outlives the borrow.
 pub struct LinkedList<T> {
-    head: Option<Box<Node<T>>>,
+    head: Link<T>,
+    /// The last node of the chain starting at `head`, `None` only for an empty list.
+    tail: Link<T>,
     size: usize,
+    _owns: PhantomData<Box<Node<T>>>,
 }
 
+// SAFETY: the list owns its nodes the same way a chain of boxes would.
+unsafe impl<T: Send> Send for LinkedList<T> {}
+unsafe impl<T: Sync> Sync for LinkedList<T> {}
+
 impl<T: Display> Display for LinkedList<T> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let mut res = ": ".to_string();
-
-        let mut current = self.head.as_ref();
-        for ind in 0..=(self.size) {
-            if let Some(node) = current {
-                if ind == 0 {
-                    res.push_str(&node.value.to_string());
-                }
-                if ind != self.size - 1 {
-                    res.push_str(" => ");
-                }
-                current = node.next.as_ref();
-                if current.is_some() {
-                    res.push_str(&current.as_ref().unwrap().value.to_string());
-                }
-            } else {
-                break;
-            }
-        }
-        write!(f, "LinkedList: Size: {}; Chain {}  ", self.size, res)
+        let chain = self
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(" => ");
+        write!(f, "LinkedList: Size: {}; Chain : {}  ", self.size, chain)
     }
 }
 
@@ -41,11 +52,19 @@ impl<T> Default for LinkedList<T> {
     }
 }
 
+impl<T> Drop for LinkedList<T> {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
 impl<T> LinkedList<T> {
     pub fn new() -> Self {
         LinkedList {
             head: None,
+            tail: None,
             size: 0,
+            _owns: PhantomData,
         }
     }
     pub fn len(&self) -> usize {
@@ -56,61 +75,140 @@ impl<T> LinkedList<T> {
         self.size == 0
     }
 
-    pub fn iter(&self) -> ListIterator<T> {
+    /// Frees the nodes one by one from the front.
+    pub fn clear(&mut self) {
+        while self.pop_front().is_some() {}
+    }
+
+    pub fn iter(&self) -> ListIterator<'_, T> {
         ListIterator {
-            current: self.head.as_deref(),
+            current: self.head,
+            _list: PhantomData,
         }
     }
 
-    pub fn push_back(&mut self, value: T) {
-        let new_node = Box::new(Node { value, next: None });
-
-        let mut last = self.head.as_mut();
-        for _ in 0..(self.size) {
-            if last.is_none() || last.as_ref().unwrap().next.is_none() {
-                break;
-            }
-            last = last.unwrap().next.as_mut();
+    pub fn iter_mut(&mut self) -> ListIterMut<'_, T> {
+        ListIterMut {
+            current: self.head,
+            _list: PhantomData,
         }
+    }
 
-        if let Some(last) = last {
-            last.next = Some(new_node);
-        } else {
-            self.head = Some(new_node);
+    pub fn push_back(&mut self, value: T) {
+        let new_node = Node { value, next: None }.into_link();
+        match self.tail {
+            // SAFETY: the tail is the last node owned by the list.
+            Some(last) => unsafe { (*last.as_ptr()).next = Some(new_node) },
+            None => self.head = Some(new_node),
         }
+        self.tail = Some(new_node);
         self.size += 1;
     }
 
     pub fn push_front(&mut self, value: T) {
-        let new_node = Box::new(Node {
+        let new_node = Node {
             value,
-            next: self.head.take(),
-        });
+            next: self.head,
+        }
+        .into_link();
+        if self.tail.is_none() {
+            self.tail = Some(new_node);
+        }
         self.head = Some(new_node);
         self.size += 1;
     }
 
+    pub fn pop_front(&mut self) -> Option<T> {
+        let head = self.head?;
+        // SAFETY: the head is owned by the list and is unlinked right away.
+        let node = unsafe { Box::from_raw(head.as_ptr()) };
+        self.head = node.next;
+        if self.head.is_none() {
+            self.tail = None;
+        }
+        self.size -= 1;
+        Some(node.value)
+    }
+
+    pub fn pop_back(&mut self) -> Option<T> {
+        let last = self.tail?;
+        if self.head == self.tail {
+            return self.pop_front();
+        }
+        let mut new_last = self.head?;
+        // SAFETY: the walk stays within the nodes owned by the list, and the last node
+        // is freed only after it is unlinked.
+        unsafe {
+            while (*new_last.as_ptr()).next != Some(last) {
+                new_last = (*new_last.as_ptr()).next?;
+            }
+            (*new_last.as_ptr()).next = None;
+            self.tail = Some(new_last);
+            self.size -= 1;
+            Some(Box::from_raw(last.as_ptr()).value)
+        }
+    }
+
+    /// Reverses the list in place by re-pointing the links of the existing nodes.
+    pub fn reverse(&mut self) {
+        self.tail = self.head;
+        let mut reversed: Link<T> = None;
+        let mut current = self.head;
+        while let Some(node) = current {
+            // SAFETY: every node is owned by the list and is visited once.
+            unsafe {
+                current = (*node.as_ptr()).next;
+                (*node.as_ptr()).next = reversed;
+            }
+            reversed = Some(node);
+        }
+        self.head = reversed;
+    }
+
+    /// Moves all nodes of the other list onto the end of this one, the other list is left empty.
+    pub fn append(&mut self, other: &mut LinkedList<T>) {
+        let Some(other_head) = other.head.take() else {
+            return;
+        };
+        match self.tail {
+            // SAFETY: the tail is the last node owned by the list.
+            Some(last) => unsafe { (*last.as_ptr()).next = Some(other_head) },
+            None => self.head = Some(other_head),
+        }
+        self.tail = other.tail.take();
+        self.size += other.size;
+        other.size = 0;
+    }
+
     pub fn insert_after(&mut self, index: usize, value: T) {
         if index == 0 {
             self.push_front(value);
             return;
         }
 
-        let mut current = self.head.as_deref_mut();
+        let mut current = self.head;
         for _ in 0..index {
             if let Some(node) = current {
-                current = node.next.as_deref_mut();
+                // SAFETY: the node is owned by the list.
+                current = unsafe { (*node.as_ptr()).next };
             } else {
                 return;
             }
         }
 
         if let Some(node) = current {
-            let new_node = Box::new(Node {
-                value,
-                next: node.next.take(),
-            });
-            node.next = Some(new_node);
+            // SAFETY: the node is owned by the list, the new node is linked right after it.
+            unsafe {
+                let new_node = Node {
+                    value,
+                    next: (*node.as_ptr()).next,
+                }
+                .into_link();
+                (*node.as_ptr()).next = Some(new_node);
+                if self.tail == Some(node) {
+                    self.tail = Some(new_node);
+                }
+            }
         }
         self.size += 1;
     }
@@ -120,58 +218,116 @@ impl<T> LinkedList<T> {
             return None;
         }
 
-        let mut current = self.head.as_deref_mut();
-        let mut count = 1;
-
-        while let Some(node) = current {
-            if count == index {
-                let next = node.next.take();
-                let list = LinkedList {
-                    head: next,
-                    size: self.size - index,
-                };
-                self.size = index;
-                return Some(list);
-            }
-
-            current = node.next.as_deref_mut();
-            count += 1;
+        let mut last = self.head?;
+        for _ in 1..index {
+            // SAFETY: the node is owned by the list.
+            last = unsafe { (*last.as_ptr()).next }?;
         }
-
-        None
+        // SAFETY: the node is owned by the list, the nodes after it move to the new list.
+        let next = unsafe { (*last.as_ptr()).next.take() };
+        let list = LinkedList {
+            head: next,
+            tail: next.and(self.tail),
+            size: self.size - index,
+            _owns: PhantomData,
+        };
+        self.tail = Some(last);
+        self.size = index;
+        Some(list)
     }
 
+    /// Replaces the value at the zero-based index, an index past the end is ignored.
     pub fn change_value_by_index(&mut self, index: usize, value: T) {
-        let mut current = self.head.as_deref_mut();
-        for _ in 0..=(index - 1) {
-            if let Some(node) = current {
-                current = node.next.as_deref_mut();
-            } else {
-                return;
-            }
+        if let Some(element) = self.iter_mut().nth(index) {
+            *element = value;
         }
-        if let Some(ref mut node) = current {
-            node.value = value;
+    }
+}
+
+impl<T: PartialEq> LinkedList<T> {
+    pub fn contains(&self, value: &T) -> bool {
+        self.position(value).is_some()
+    }
+
+    /// Returns the index of the first node holding the value.
+    pub fn position(&self, value: &T) -> Option<usize> {
+        self.iter().position(|item| item == value)
+    }
+}
+
+impl<T> FromIterator<T> for LinkedList<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut list = LinkedList::new();
+        for value in iter {
+            list.push_back(value);
         }
+        list
+    }
+}
+
+impl<T> IntoIterator for LinkedList<T> {
+    type Item = T;
+    type IntoIter = ListIntoIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        ListIntoIter { list: self }
+    }
+}
+
+/// The owning iterator of a [`LinkedList`], yields the values from the front.
+pub struct ListIntoIter<T> {
+    list: LinkedList<T>,
+}
+
+impl<T> Iterator for ListIntoIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.list.pop_front()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.list.len(), Some(self.list.len()))
     }
 }
 
 pub struct ListIterator<'a, T> {
-    current: Option<&'a Node<T>>,
+    current: Link<T>,
+    _list: PhantomData<&'a Node<T>>,
 }
 
 impl<'a, T> Iterator for ListIterator<'a, T> {
     type Item = &'a T;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if let Some(node) = self.current {
-            self.current = node.next.as_deref();
-            Some(&node.value)
-        } else {
-            None
-        }
+        self.current.map(|node| {
+            // SAFETY: the node is owned by the list borrowed for `'a`.
+            let node = unsafe { &*node.as_ptr() };
+            self.current = node.next;
+            &node.value
+        })
     }
 }
+
+pub struct ListIterMut<'a, T> {
+    current: Link<T>,
+    _list: PhantomData<&'a mut Node<T>>,
+}
+
+impl<'a, T> Iterator for ListIterMut<'a, T> {
+    type Item = &'a mut T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.current.map(|node| {
+            // SAFETY: the node is owned by the list mutably borrowed for `'a`,
+            // every node is yielded once.
+            let node = unsafe { &mut *node.as_ptr() };
+            self.current = node.next;
+            &mut node.value
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::LinkedList;
@@ -250,6 +406,19 @@ mod tests {
         assert_eq!(result, vec![1, 2, 3]);
     }
 
+    #[test]
+    fn test_iter_mut() {
+        let mut list: LinkedList<i32> = LinkedList::new();
+        list.push_back(1);
+        list.push_back(2);
+        list.push_back(3);
+
+        list.iter_mut().for_each(|value| *value *= 2);
+
+        let result: Vec<i32> = list.iter().copied().collect();
+        assert_eq!(result, vec![2, 4, 6]);
+    }
+
     #[test]
     fn test_push_front_and_back() {
         let mut list: LinkedList<i32> = LinkedList::new();
@@ -351,4 +520,199 @@ mod tests {
         let result: Vec<i32> = list.iter().copied().collect();
         assert_eq!(result, vec![1, 4, 3]);
     }
+
+    #[test]
+    fn test_clear() {
+        let mut list: LinkedList<i32> = LinkedList::new();
+        for value in 0..100_000 {
+            list.push_front(value);
+        }
+        list.clear();
+        assert!(list.is_empty());
+        assert_eq!(list.len(), 0);
+        assert_eq!(list.iter().count(), 0);
+
+        list.push_back(1);
+        list.push_front(0);
+        assert_eq!(list.iter().copied().collect::<Vec<i32>>(), vec![0, 1]);
+    }
+
+    #[test]
+    fn test_pop_empty() {
+        let mut list: LinkedList<i32> = LinkedList::new();
+        assert_eq!(list.pop_front(), None);
+        assert_eq!(list.pop_back(), None);
+        assert_eq!(list.len(), 0);
+    }
+
+    #[test]
+    fn test_pop_single_element() {
+        let mut list: LinkedList<i32> = LinkedList::new();
+        list.push_back(1);
+        assert_eq!(list.pop_back(), Some(1));
+        assert!(list.is_empty());
+        assert!(list.head.is_none());
+
+        list.push_back(2);
+        assert_eq!(list.pop_front(), Some(2));
+        assert!(list.is_empty());
+        assert!(list.head.is_none());
+    }
+
+    #[test]
+    fn test_push_and_pop_alternating() {
+        let mut list: LinkedList<i32> = LinkedList::new();
+        list.push_back(1);
+        list.push_front(0);
+        list.push_back(2);
+        assert_eq!(list.pop_back(), Some(2));
+        assert_eq!(list.len(), 2);
+        list.push_back(3);
+        assert_eq!(list.pop_front(), Some(0));
+        assert_eq!(list.len(), 2);
+        assert_eq!(list.iter().copied().collect::<Vec<i32>>(), vec![1, 3]);
+        assert_eq!(list.pop_back(), Some(3));
+        assert_eq!(list.pop_back(), Some(1));
+        assert_eq!(list.pop_back(), None);
+        assert_eq!(list.len(), 0);
+    }
+
+    #[test]
+    fn test_change_value_by_index_bounds() {
+        let mut list: LinkedList<i32> = LinkedList::new();
+        list.push_back(1);
+        list.push_back(2);
+        list.push_back(3);
+        list.change_value_by_index(0, 10);
+        list.change_value_by_index(2, 30);
+        list.change_value_by_index(3, 40);
+        assert_eq!(list.iter().copied().collect::<Vec<i32>>(), vec![10, 2, 30]);
+        assert_eq!(list.len(), 3);
+
+        let mut empty: LinkedList<i32> = LinkedList::new();
+        empty.change_value_by_index(0, 1);
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn test_from_iter() {
+        let list: LinkedList<i32> = (1..=3).collect();
+        assert_eq!(list.len(), 3);
+        assert_eq!(list.iter().copied().collect::<Vec<i32>>(), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_into_iter() {
+        let mut list: LinkedList<String> = LinkedList::new();
+        list.push_back("a".to_string());
+        list.push_back("b".to_string());
+        list.push_back("c".to_string());
+
+        let mut values = list.into_iter();
+        assert_eq!(values.size_hint(), (3, Some(3)));
+        assert_eq!(values.next(), Some("a".to_string()));
+        assert_eq!(values.collect::<Vec<_>>(), vec!["b", "c"]);
+    }
+
+    #[test]
+    fn test_reverse() {
+        let mut empty: LinkedList<i32> = LinkedList::new();
+        empty.reverse();
+        assert!(empty.is_empty());
+
+        let mut single: LinkedList<i32> = (1..=1).collect();
+        single.reverse();
+        assert_eq!(single.iter().copied().collect::<Vec<i32>>(), vec![1]);
+
+        let mut list: LinkedList<i32> = (1..=3).collect();
+        list.reverse();
+        assert_eq!(list.len(), 3);
+        assert_eq!(list.iter().copied().collect::<Vec<i32>>(), vec![3, 2, 1]);
+
+        list.push_back(0);
+        assert_eq!(list.len(), 4);
+        assert_eq!(list.iter().copied().collect::<Vec<i32>>(), vec![3, 2, 1, 0]);
+    }
+
+    #[test]
+    fn test_append() {
+        let mut list: LinkedList<i32> = (1..=2).collect();
+        let mut other: LinkedList<i32> = (3..=4).collect();
+        list.append(&mut other);
+        assert_eq!(list.len(), 4);
+        assert_eq!(list.iter().copied().collect::<Vec<i32>>(), vec![1, 2, 3, 4]);
+        assert_eq!(other.len(), 0);
+        assert!(other.iter().next().is_none());
+
+        let mut empty: LinkedList<i32> = LinkedList::new();
+        empty.append(&mut list);
+        assert_eq!(empty.len(), 4);
+        assert!(list.is_empty());
+    }
+
+    #[test]
+    fn test_contains_and_position() {
+        let list: LinkedList<i32> = (1..=3).collect();
+        assert!(list.contains(&1));
+        assert_eq!(list.position(&1), Some(0));
+        assert!(list.contains(&2));
+        assert_eq!(list.position(&2), Some(1));
+        assert!(!list.contains(&4));
+        assert_eq!(list.position(&4), None);
+
+        let empty: LinkedList<i32> = LinkedList::new();
+        assert!(!empty.contains(&1));
+        assert_eq!(empty.position(&1), None);
+    }
+
+    #[test]
+    fn test_push_back_many() {
+        let mut list = LinkedList::new();
+        for value in 0..10_000 {
+            list.push_back(value);
+        }
+        assert_eq!(list.len(), 10_000);
+        assert!(list.iter().copied().eq(0..10_000));
+    }
+
+    #[test]
+    fn test_push_back_after_changes() {
+        let mut list: LinkedList<i32> = (1..=4).collect();
+        list.pop_back();
+        list.push_back(5);
+        let mut tail = list.split_at(2).unwrap();
+        list.push_back(6);
+        tail.push_back(7);
+        assert_eq!(list.iter().copied().collect::<Vec<i32>>(), vec![1, 2, 6]);
+        assert_eq!(tail.iter().copied().collect::<Vec<i32>>(), vec![3, 5, 7]);
+
+        list.append(&mut tail);
+        tail.push_back(8);
+        list.insert_after(5, 9);
+        list.push_back(10);
+        assert_eq!(
+            list.iter().copied().collect::<Vec<i32>>(),
+            vec![1, 2, 6, 3, 5, 7, 9, 10]
+        );
+        assert_eq!(tail.iter().copied().collect::<Vec<i32>>(), vec![8]);
+
+        while list.pop_front().is_some() {}
+        list.push_back(11);
+        assert_eq!(list.iter().copied().collect::<Vec<i32>>(), vec![11]);
+    }
+
+    #[test]
+    fn test_push_back_after_borrowing_nodes() {
+        let mut list: LinkedList<i32> = (1..=3).collect();
+        list.iter_mut().for_each(|value| *value *= 10);
+        list.push_back(4);
+        list.change_value_by_index(3, 40);
+        list.push_back(5);
+        list.pop_back();
+        list.push_back(50);
+        assert_eq!(
+            list.iter().copied().collect::<Vec<i32>>(),
+            vec![10, 20, 30, 40, 50]
+        );
+    }
 }
@@ -6,21 +6,32 @@
 use log::{debug, error, info};
 use rand::prelude::*;
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
 use std::cell::RefCell;
-use std::collections::{BTreeMap, HashMap};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt::{Display, Formatter};
 use thiserror::Error;
 
-type Money = f64;
+pub use crate::money::Money;
 pub type Result<T, E = BankError> = std::result::Result<T, E>;
 
-const MONEY_ZERO: Money = 0.0;
+const MONEY_ZERO: Money = Money::ZERO;
+
+/// The category of the operations recorded by [`BankAdmin::set_balance`].
+pub const BALANCE_ADJUSTMENT_CATEGORY: &str = "balance adjustment";
 
 pub type TransactionId = String;
 
 pub enum BankResponse {
     Transaction(Result<TransactionId>),
+    /// The id of the operation already applied with the same idempotency key, nothing is applied.
+    Replayed(TransactionId),
     History(Result<Vec<Operation>>),
     Balance(Result<Money>),
+    HistoryLen(usize),
+    Imported(usize, Vec<TransactionId>),
+    Operation(Option<Operation>),
+    Accounts(Vec<String>),
 }
 
 #[derive(Default)]
@@ -29,6 +40,58 @@ pub struct Bank {
     accounts_history: HashMap<String, Vec<TransactionId>>,
     history: BTreeMap<TransactionId, Operation>,
     ulid_generator: ulid::Generator,
+    account_name_policy: AccountNamePolicy,
+    fee_account: Option<String>,
+    idempotency_keys: HashMap<String, TransactionId>,
+    overdraft_limits: HashMap<String, Money>,
+    frozen_accounts: HashSet<String>,
+    /// The maximum balance a deposit or a transfer can reach, [`Money::MAX`] if not set.
+    max_balance: Option<Money>,
+    /// Makes the account codes trimmed and case-insensitive, see [`Bank::with_account_normalization`].
+    normalize_accounts: bool,
+    /// Makes the next `push_transaction` fail, to check that the operations are atomic.
+    #[cfg(test)]
+    fail_next_push: bool,
+}
+
+/// The state of a [`Bank`] that can be persisted, see [`Bank::snapshot`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BankSnapshot {
+    balances: BTreeMap<String, Money>,
+    history: BTreeMap<TransactionId, Operation>,
+}
+
+/// The rules the account names must follow to be created.
+#[derive(Debug, Clone)]
+pub struct AccountNamePolicy {
+    /// The maximum number of characters in the name.
+    pub max_length: usize,
+    /// Returns `true` for the characters allowed in the name.
+    pub allowed_chars: fn(char) -> bool,
+}
+
+impl Default for AccountNamePolicy {
+    /// Allows any non-empty name.
+    fn default() -> Self {
+        Self {
+            max_length: usize::MAX,
+            allowed_chars: |_| true,
+        }
+    }
+}
+
+impl AccountNamePolicy {
+    fn validate(&self, account: &str) -> Result<(), InvalidAccountNameError> {
+        if account.is_empty()
+            || account.chars().count() > self.max_length
+            || !account.chars().all(self.allowed_chars)
+        {
+            return Err(InvalidAccountNameError {
+                account: account.to_owned(),
+            });
+        }
+        Ok(())
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -38,6 +101,43 @@ pub struct Operation {
     source_account: String,
     amount: Money,
     operation_type: OperationType,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    category: Option<String>,
+}
+
+impl Operation {
+    /// Creates an operation, the single place where all of its fields are filled.
+    pub fn new(
+        id: TransactionId,
+        account: &str,
+        amount: impl Into<Money>,
+        operation_type: OperationType,
+    ) -> Self {
+        Self {
+            id,
+            source_account: account.to_owned(),
+            amount: amount.into(),
+            operation_type,
+            category: None,
+        }
+    }
+
+    /// Returns how the operation changed the balance of the account.
+    fn balance_change(&self, account: &str) -> Money {
+        match &self.operation_type {
+            OperationType::Deposit if self.source_account == account => self.amount,
+            OperationType::Withdraw if self.source_account == account => -self.amount,
+            OperationType::Transfer { .. } if self.source_account == account => -self.amount,
+            OperationType::Transfer { target_account } if target_account == account => self.amount,
+            _ => MONEY_ZERO,
+        }
+    }
+
+    /// Tags the operation with the category, e.g. "salary" or "groceries".
+    pub fn with_category(mut self, category: Option<String>) -> Self {
+        self.category = category;
+        self
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -46,6 +146,7 @@ pub enum OperationType {
     Deposit,
     Withdraw,
     Transfer { target_account: String },
+    CloseAccount,
 }
 
 #[derive(Debug, Error, PartialEq)]
@@ -60,6 +161,36 @@ pub struct AccountNotFoundError {
     account: String,
 }
 
+#[derive(Debug, Error, PartialEq)]
+pub enum HistoryCorruptedError {
+    #[error("Operation `{transaction_id}` is missing from the history")]
+    MissingOperation { transaction_id: TransactionId },
+    #[error("Creation of account `{account}` is missing from the history")]
+    MissingAccountCreation { account: String },
+}
+
+#[macro_export]
+macro_rules! normalize_account {
+    ($self: expr, $($account: ident),+) => {
+        $(
+            let $account = $self.account_key($account);
+            let $account: &str = &$account;
+        )+
+    };
+}
+
+#[derive(Debug, Error, PartialEq)]
+#[error("Operation `{transaction_id}` does not exist")]
+pub struct OperationNotFoundError {
+    transaction_id: TransactionId,
+}
+
+#[derive(Debug, Error, PartialEq)]
+#[error("Invalid account name `{account}`")]
+pub struct InvalidAccountNameError {
+    account: String,
+}
+
 #[macro_export]
 macro_rules! check_account_exists {
     ($self: expr , $account: expr) => {{
@@ -70,6 +201,22 @@ macro_rules! check_account_exists {
     }};
 }
 
+#[macro_export]
+macro_rules! check_account_not_frozen {
+    ($self: expr , $account: expr) => {{
+        if $self.frozen_accounts.contains(&$account) {
+            error!("Account {} is frozen", $account);
+            return Err(AccountFrozenError { account: $account }.into());
+        }
+    }};
+}
+
+#[derive(Debug, Error, PartialEq)]
+#[error("Account `{account}` is frozen")]
+pub struct AccountFrozenError {
+    account: String,
+}
+
 #[derive(Debug, Error, PartialEq)]
 #[error("Cannot transfer to the same account")]
 pub struct SomeAccountTransferError {
@@ -83,6 +230,13 @@ pub struct AmountNegativeError {
     amount: Money,
 }
 
+#[derive(Debug, Error, PartialEq)]
+#[error("Account `{account}` can not be closed with the balance `{balance}`")]
+pub struct NonZeroBalanceError {
+    account: String,
+    balance: Money,
+}
+
 #[derive(Debug, Error, PartialEq)]
 #[error("Insufficient funds for account `{0}` available `{1}` requested `{2}`", .account, .amount, .balance)]
 pub struct InsufficientFundsError {
@@ -91,6 +245,16 @@ pub struct InsufficientFundsError {
     balance: Money,
 }
 
+#[derive(Debug, Error, PartialEq)]
+#[error(
+    "Crediting `{amount}` would make the balance of account `{account}` exceed `{max_balance}`"
+)]
+pub struct BalanceOverflowError {
+    account: String,
+    amount: Money,
+    max_balance: Money,
+}
+
 #[derive(Debug, Error, PartialEq)]
 pub enum BankError {
     #[error("Account already exists")]
@@ -103,6 +267,27 @@ pub enum BankError {
     InsufficientFunds(#[from] InsufficientFundsError),
     #[error("Cannot transfer to the same account")]
     SomeAccountTransfer(#[from] SomeAccountTransferError),
+    #[error("Invalid account name")]
+    InvalidAccountName(#[from] InvalidAccountNameError),
+    #[error("History is corrupted")]
+    HistoryCorrupted(#[from] HistoryCorruptedError),
+    #[error("Account balance is not zero")]
+    NonZeroBalance(#[from] NonZeroBalanceError),
+    #[error("Operation does not exist")]
+    OperationNotFound(#[from] OperationNotFoundError),
+    #[error("Account is frozen")]
+    AccountFrozen(#[from] AccountFrozenError),
+    #[error("Balance limit exceeded")]
+    BalanceOverflow(#[from] BalanceOverflowError),
+}
+
+/// Checks whether the balance together with the overdraft limit covers the amount.
+///
+/// A sum beyond the range of `Money` covers any amount.
+fn has_sufficient_funds(balance: Money, amount: Money, overdraft: Money) -> bool {
+    balance
+        .checked_add(overdraft)
+        .map_or(true, |available| available >= amount)
 }
 
 impl BankError {
@@ -110,6 +295,61 @@ impl BankError {
         error!("Account {} does not exist", account);
         AccountNotFoundError { account }.into()
     }
+
+    /// Checks whether the error is [`BankError::AccountDuplication`].
+    pub fn is_account_duplication(&self) -> bool {
+        matches!(self, BankError::AccountDuplication(_))
+    }
+
+    /// Checks whether the error is [`BankError::AmountNegative`].
+    pub fn is_amount_negative(&self) -> bool {
+        matches!(self, BankError::AmountNegative(_))
+    }
+
+    /// Checks whether the error is [`BankError::AccountNotFound`].
+    pub fn is_account_not_found(&self) -> bool {
+        matches!(self, BankError::AccountNotFound(_))
+    }
+
+    /// Checks whether the error is [`BankError::InsufficientFunds`].
+    pub fn is_insufficient_funds(&self) -> bool {
+        matches!(self, BankError::InsufficientFunds(_))
+    }
+
+    /// Checks whether the error is [`BankError::SomeAccountTransfer`].
+    pub fn is_some_account_transfer(&self) -> bool {
+        matches!(self, BankError::SomeAccountTransfer(_))
+    }
+
+    /// Checks whether the error is [`BankError::InvalidAccountName`].
+    pub fn is_invalid_account_name(&self) -> bool {
+        matches!(self, BankError::InvalidAccountName(_))
+    }
+
+    /// Checks whether the error is [`BankError::HistoryCorrupted`].
+    pub fn is_history_corrupted(&self) -> bool {
+        matches!(self, BankError::HistoryCorrupted(_))
+    }
+
+    /// Checks whether the error is [`BankError::NonZeroBalance`].
+    pub fn is_non_zero_balance(&self) -> bool {
+        matches!(self, BankError::NonZeroBalance(_))
+    }
+
+    /// Checks whether the error is [`BankError::OperationNotFound`].
+    pub fn is_operation_not_found(&self) -> bool {
+        matches!(self, BankError::OperationNotFound(_))
+    }
+
+    /// Checks whether the error is [`BankError::AccountFrozen`].
+    pub fn is_account_frozen(&self) -> bool {
+        matches!(self, BankError::AccountFrozen(_))
+    }
+
+    /// Checks whether the error is [`BankError::BalanceOverflow`].
+    pub fn is_balance_overflow(&self) -> bool {
+        matches!(self, BankError::BalanceOverflow(_))
+    }
 }
 
 impl Bank {
@@ -117,158 +357,597 @@ impl Bank {
         Self::default()
     }
 
-    fn get_next_id(&mut self) -> String {
-        self.ulid_generator
-            .generate_with_source(&mut StdRng::from_entropy())
-            .unwrap()
-            .to_string()
-    }
-
-    fn push_transaction(&mut self, operation: Operation) -> Result<(), BankError> {
-        if !self.accounts.contains_key(&operation.source_account) {
-            return Err(BankError::account_not_found(operation.source_account));
-        }
-        // check target account exists when transferring
-        if let OperationType::Transfer { ref target_account } = operation.operation_type {
-            if !self.accounts.contains_key(target_account) {
-                return Err(BankError::account_not_found(target_account.to_string()));
+    /// Creates a bank with the accounts holding the balances, e.g. for property testing.
+    ///
+    /// The balances are set by recorded operations, so the history stays consistent with them:
+    /// a positive balance is deposited, a negative one is withdrawn within an overdraft limit.
+    ///
+    /// # Panics
+    ///
+    /// Panics if an account name is duplicated or does not follow the account name policy.
+    pub fn from_balances(balances: &[(&str, Money)]) -> Bank {
+        let mut bank = Bank::new();
+        for (account, balance) in balances {
+            bank.create_account(account)
+                .expect("the balance table must hold unique valid account names");
+            if *balance > MONEY_ZERO {
+                bank.deposit(account, *balance).unwrap();
+            } else if *balance < MONEY_ZERO {
+                bank.set_overdraft_limit(account, -*balance).unwrap();
+                bank.withdraw(account, -*balance).unwrap();
             }
         }
-
-        let account = operation.source_account.clone();
-        self.accounts_history
-            .entry(account)
-            .or_default()
-            .push(operation.id.clone());
-
-        if let OperationType::Transfer { ref target_account } = operation.operation_type {
-            let target_account = target_account.clone();
-            self.accounts_history
-                .get_mut(&target_account)
-                .unwrap()
-                .push(operation.id.clone());
-        }
-        self.history.insert(operation.id.clone(), operation);
-        Ok(())
+        bank
     }
-}
 
-impl BankTrait for Bank {
-    /// Creates a new account with the specified name and adds it to the bank.
+    /// Creates the account and deposits the initial balance into it, so the history stays consistent.
     ///
     /// # Arguments
     ///
     /// * `account` - The code of the account to create.
+    /// * `initial` - The initial balance, nothing is deposited when it is zero.
+    ///
+    /// # Returns
+    /// The transaction id of the account creation.
     ///
     /// # Errors
+    /// AmountNegativeError, the account is not created then
     /// AccountDuplicationError
-    ///
-    /// Result
-    /// TransactionId for the new account
-    /// Returns an error if an account with the same name already exists in the bank.
-    ///
-    /// ```
-    fn create_account(&mut self, account: &str) -> Result<TransactionId> {
-        if self.accounts.contains_key(account) {
-            error!("Account already exists");
-            return Err(AccountDuplicationError {
+    /// InvalidAccountNameError
+    pub fn create_account_with_balance(
+        &mut self,
+        account: &str,
+        initial: impl Into<Money>,
+    ) -> Result<TransactionId> {
+        let initial = initial.into();
+        if initial < MONEY_ZERO {
+            error!("Initial balance must not be negative");
+            return Err(AmountNegativeError {
                 account: account.to_owned(),
+                amount: initial,
             }
             .into());
         }
+        let transaction_id = self.create_account(account)?;
+        if initial > MONEY_ZERO {
+            self.deposit(account, initial)?;
+        }
+        Ok(transaction_id)
+    }
 
-        let next_id = self.get_next_id();
+    /// Returns the sum of the balances of all open accounts.
+    pub fn total_balance(&self) -> Money {
         self.accounts
-            .insert(account.to_owned(), RefCell::from(MONEY_ZERO));
-        let operation = Operation {
-            id: next_id.clone(),
-            source_account: account.to_owned(),
-            amount: MONEY_ZERO,
-            operation_type: OperationType::CreateAccount,
+            .values()
+            .map(|balance| *balance.borrow())
+            .sum()
+    }
+
+    /// Sets the maximum balance a deposit or a transfer can reach, the ones beyond it fail with
+    /// [`BankError::BalanceOverflow`].
+    pub fn with_max_balance(mut self, max_balance: impl Into<Money>) -> Self {
+        self.max_balance = Some(max_balance.into());
+        self
+    }
+
+    /// Trims the account codes and folds their case, so e.g. `Alice` and ` alice ` are the same
+    /// account. The accounts are stored under the normalized codes.
+    pub fn with_account_normalization(mut self) -> Self {
+        self.normalize_accounts = true;
+        self
+    }
+
+    /// Returns the code the account is stored under.
+    fn account_key<'a>(&self, account: &'a str) -> Cow<'a, str> {
+        if self.normalize_accounts {
+            Cow::Owned(account.trim().to_lowercase())
+        } else {
+            Cow::Borrowed(account)
+        }
+    }
+
+    /// Sets the rules the names of the new accounts must follow.
+    pub fn with_account_name_policy(mut self, policy: AccountNamePolicy) -> Self {
+        self.account_name_policy = policy;
+        self
+    }
+
+    /// Performs the operation once per idempotency key, so a retried request is not applied twice.
+    ///
+    /// # Arguments
+    ///
+    /// * `idempotency_key` - The key of the operation, the operation is always performed without it.
+    /// * `operation` - The operation to perform.
+    ///
+    /// # Returns
+    /// The id of the transaction the key was first used for, or of the performed operation.
+    /// A failed operation does not consume the key.
+    pub fn apply_once(
+        &mut self,
+        idempotency_key: Option<String>,
+        operation: impl FnOnce(&mut Bank) -> Result<TransactionId>,
+    ) -> Result<TransactionId> {
+        let Some(idempotency_key) = idempotency_key else {
+            return operation(self);
         };
-        self.push_transaction(operation)?;
-        info!("Created account {}", &account);
-        Ok(next_id)
+        if let Some(transaction_id) = self.idempotency_keys.get(&idempotency_key) {
+            info!(
+                "Operation with the key {} is already applied",
+                idempotency_key
+            );
+            return Ok(transaction_id.to_owned());
+        }
+        let transaction_id = operation(self)?;
+        self.idempotency_keys
+            .insert(idempotency_key, transaction_id.clone());
+        Ok(transaction_id)
     }
 
-    /// Deposits the specified amount into the account.
+    /// Checks whether an operation has already been applied with the idempotency key,
+    /// see [`Bank::apply_once`].
+    pub fn is_idempotency_key_used(&self, idempotency_key: &str) -> bool {
+        self.idempotency_keys.contains_key(idempotency_key)
+    }
+
+    /// Allows the balance of the account to go down to `-limit`.
     ///
     /// # Arguments
     ///
-    /// * `amount` - The amount to deposit into the account.
-    /// * `account` - The account to deposit into.
+    /// * `account` - The code of the account.
+    /// * `limit` - The overdraft limit, zero disables the overdraft.
     ///
-    /// Result
-    /// `TransactionId` for operation
     /// # Errors
-    /// AmountNegativeError
     /// AccountNotFoundError
-    ///
-    /// ```
-    fn deposit(&mut self, account: &str, amount: Money) -> Result<TransactionId, BankError> {
+    /// AmountNegativeError if the limit is negative
+    pub fn set_overdraft_limit(&mut self, account: &str, limit: impl Into<Money>) -> Result<()> {
+        normalize_account!(self, account);
+        let limit = limit.into();
         check_account_exists!(self, account.to_string());
-
-        if let Some(balance) = self.accounts.get_mut(account) {
-            if amount <= Money::default() {
-                error!("Amount must be positive");
-                Err(AmountNegativeError {
-                    account: account.to_owned(),
-                    amount,
-                }
-                .into())
-            } else {
-                *balance.get_mut() += amount;
-                let transaction_id = self.get_next_id();
-                let operation = Operation {
-                    id: transaction_id.to_owned(),
-                    source_account: account.to_owned(),
-                    amount,
-                    operation_type: OperationType::Deposit,
-                };
-                self.push_transaction(operation)?;
-                info!("Deposited into account {}", &account);
-                Ok(transaction_id.to_owned())
+        if limit < MONEY_ZERO {
+            error!("Overdraft limit must not be negative");
+            return Err(AmountNegativeError {
+                account: account.to_owned(),
+                amount: limit,
             }
-        } else {
-            Err(BankError::account_not_found(account.to_string()))
+            .into());
         }
+        info!("Overdraft limit of account {} set to {}", account, limit);
+        self.overdraft_limits.insert(account.to_owned(), limit);
+        Ok(())
     }
 
-    /// Withdraws the specified amount from the account.
+    /// Returns the overdraft limit of the account, zero if it is not set.
+    pub fn overdraft_limit(&self, account: &str) -> Money {
+        normalize_account!(self, account);
+        self.overdraft_limits
+            .get(account)
+            .copied()
+            .unwrap_or(MONEY_ZERO)
+    }
+
+    /// Freezes the account, its balance can still be read but not changed until it is unfrozen.
+    ///
+    /// # Errors
+    /// AccountNotFoundError
+    pub fn freeze_account(&mut self, account: &str) -> Result<()> {
+        normalize_account!(self, account);
+        check_account_exists!(self, account.to_string());
+        info!("Froze account {}", account);
+        self.frozen_accounts.insert(account.to_owned());
+        Ok(())
+    }
+
+    /// Unfreezes the account, so it can be changed again.
+    ///
+    /// # Errors
+    /// AccountNotFoundError
+    pub fn unfreeze_account(&mut self, account: &str) -> Result<()> {
+        normalize_account!(self, account);
+        check_account_exists!(self, account.to_string());
+        info!("Unfroze account {}", account);
+        self.frozen_accounts.remove(account);
+        Ok(())
+    }
+
+    /// Checks whether the account is frozen.
+    pub fn is_frozen(&self, account: &str) -> bool {
+        normalize_account!(self, account);
+        self.frozen_accounts.contains(account)
+    }
+
+    /// Returns the balance of the account right after the transaction.
     ///
     /// # Arguments
     ///
-    /// * `amount` - The amount to withdraw from the account.
-    /// * `account` - The account to withdraw from.
+    /// * `account` - The code of the account.
+    /// * `transaction_id` - The last transaction to take into account.
     ///
     /// # Errors
-    /// AmountNegativeError
     /// AccountNotFoundError
-    /// InsufficientFundsError
+    /// OperationNotFoundError if the transaction is not in the history
+    pub fn get_balance_at(&self, account: &str, transaction_id: &TransactionId) -> Result<Money> {
+        normalize_account!(self, account);
+        if !self.accounts_history.contains_key(account) {
+            check_account_exists!(self, account.to_string());
+        }
+        if !self.history.contains_key(transaction_id) {
+            error!("Operation {} does not exist", transaction_id);
+            return Err(OperationNotFoundError {
+                transaction_id: transaction_id.to_owned(),
+            }
+            .into());
+        }
+        Ok(self
+            .history
+            .range(..=transaction_id.to_owned())
+            .map(|(_, operation)| operation.balance_change(account))
+            .sum())
+    }
+
+    /// Returns the history of the account with the balance of the account after every operation.
     ///
-    /// Returns an error if the account balance is insufficient to cover the withdrawal amount.
+    /// # Errors
+    /// AccountNotFoundError
+    /// HistoryCorruptedError
+    pub fn account_statement(&self, account: &str) -> Result<Vec<(Operation, Money)>> {
+        normalize_account!(self, account);
+        let mut balance = MONEY_ZERO;
+        Ok(self
+            .get_account_history(account)?
+            .into_iter()
+            .map(|operation| {
+                balance += operation.balance_change(account);
+                (operation.clone(), balance)
+            })
+            .collect())
+    }
+
+    /// Returns the signed effect of the operation on the account, for summing up exported history.
     ///
-    /// ```
-    fn withdraw(&mut self, account: &str, amount: Money) -> Result<TransactionId, BankError> {
-        check_account_exists!(self, account.to_string());
+    /// Deposits are positive and withdrawals negative, a transfer is negative for the sender
+    /// and positive for the receiver, operations of other accounts have no effect.
+    pub fn signed_amount_for(&self, op: &Operation, account: &str) -> Money {
+        normalize_account!(self, account);
+        op.balance_change(account)
+    }
 
-        let transaction_id = self.get_next_id();
-        let operation = Operation {
-            id: transaction_id.to_owned(),
-            source_account: account.to_owned(),
-            amount,
-            operation_type: OperationType::Withdraw,
-        };
+    /// Distributes the money of the sender among the receivers, either to all of them or none.
+    ///
+    /// # Arguments
+    ///
+    /// * `sender_account` - The account from which the amounts will be transferred.
+    /// * `receivers` - The receivers with their amounts, in order.
+    ///
+    /// # Returns
+    /// The transaction ids of the transfers, in the order of the receivers.
+    ///
+    /// # Errors
+    /// The first error of [`BankTrait::transfer_batch`], nothing is transferred then.
+    pub fn transfer_split(
+        &mut self,
+        sender_account: &str,
+        receivers: &[(&str, Money)],
+    ) -> Result<Vec<TransactionId>> {
+        let transfers = receivers
+            .iter()
+            .map(|(receiver_account, amount)| {
+                (
+                    sender_account.to_owned(),
+                    receiver_account.to_string(),
+                    *amount,
+                )
+            })
+            .collect::<Vec<_>>();
+        self.transfer_batch(&transfers)
+    }
 
-        if let Some(balance) = self.accounts.get_mut(account) {
-            if amount <= Money::default() {
-                error!("Amount must be positive: amount {amount}");
-                return Err(AmountNegativeError {
+    /// Closes every account with a zero balance, the funded accounts are left alone.
+    ///
+    /// # Returns
+    /// The codes of the closed accounts sorted alphabetically.
+    pub fn close_empty_accounts(&mut self) -> Vec<String> {
+        let empty_accounts = self
+            .list_accounts()
+            .into_iter()
+            .filter(|account| self.get_balance(account) == Ok(MONEY_ZERO))
+            .collect::<Vec<_>>();
+        empty_accounts
+            .into_iter()
+            .filter(|account| self.close_account(account).is_ok())
+            .collect()
+    }
+
+    /// Designates the account that collects the transfer fees.
+    ///
+    /// The fees already collected stay on the previous fee account.
+    ///
+    /// # Errors
+    /// AccountNotFoundError
+    pub fn set_fee_account(&mut self, account: &str) -> Result<()> {
+        normalize_account!(self, account);
+        check_account_exists!(self, account.to_string());
+        info!("Fee account set to {}", account);
+        self.fee_account = Some(account.to_owned());
+        Ok(())
+    }
+
+    /// Returns the account that collects the transfer fees, if any.
+    pub fn fee_account(&self) -> Option<&str> {
+        self.fee_account.as_deref()
+    }
+
+    /// Transfers the amount and moves the fee from the sender to the fee account.
+    ///
+    /// The fee is recorded as a separate transfer, it is not charged while no fee account
+    /// is set or when the sender is the fee account itself. Both transfers are performed
+    /// as a [`BankTrait::transfer_batch`], so nothing is moved when either of them fails.
+    ///
+    /// # Returns
+    /// The id of the transfer of the amount.
+    ///
+    /// # Errors
+    /// AmountNegativeError
+    /// AccountNotFoundError
+    /// AccountFrozenError, also when the fee account is frozen
+    /// InsufficientFundsError if the balance does not cover both the amount and the fee,
+    /// or their sum overflows
+    /// SomeAccountTransferError
+    pub fn transfer_with_fee(
+        &mut self,
+        sender_account: &str,
+        receiver_account: &str,
+        amount: impl Into<Money>,
+        fee: impl Into<Money>,
+    ) -> Result<TransactionId> {
+        normalize_account!(self, sender_account, receiver_account);
+        let (amount, fee) = (amount.into(), fee.into());
+        let fee_account = self
+            .fee_account
+            .clone()
+            .filter(|fee_account| fee > MONEY_ZERO && fee_account != sender_account);
+        let Some(fee_account) = fee_account else {
+            return self.transfer(sender_account, receiver_account, amount);
+        };
+
+        let balance = self.get_balance(sender_account)?;
+        let overdraft_limit = self.overdraft_limit(sender_account);
+        let total = amount.checked_add(fee);
+        if !total.is_some_and(|total| has_sufficient_funds(balance, total, overdraft_limit)) {
+            error!("Insufficient funds for the transfer with the fee");
+            return Err(InsufficientFundsError {
+                account: sender_account.to_owned(),
+                amount: total.unwrap_or(Money::MAX),
+                balance,
+            }
+            .into());
+        }
+        let mut transaction_ids = self.transfer_batch(&[
+            (
+                sender_account.to_owned(),
+                receiver_account.to_owned(),
+                amount,
+            ),
+            (sender_account.to_owned(), fee_account, fee),
+        ])?;
+        Ok(transaction_ids.remove(0))
+    }
+
+    fn get_next_id(&mut self) -> String {
+        self.ulid_generator.generate().unwrap().to_string()
+    }
+
+    fn push_transaction(&mut self, operation: Operation) -> Result<(), BankError> {
+        #[cfg(test)]
+        if std::mem::take(&mut self.fail_next_push) {
+            return Err(BankError::account_not_found(operation.source_account));
+        }
+        if !self.accounts.contains_key(&operation.source_account) {
+            return Err(BankError::account_not_found(operation.source_account));
+        }
+        // check target account exists when transferring
+        if let OperationType::Transfer { ref target_account } = operation.operation_type {
+            if !self.accounts.contains_key(target_account) {
+                return Err(BankError::account_not_found(target_account.to_string()));
+            }
+        }
+
+        let account = operation.source_account.clone();
+        self.accounts_history
+            .entry(account)
+            .or_default()
+            .push(operation.id.clone());
+
+        if let OperationType::Transfer { ref target_account } = operation.operation_type {
+            let target_account = target_account.clone();
+            self.accounts_history
+                .get_mut(&target_account)
+                .unwrap()
+                .push(operation.id.clone());
+        }
+        self.history.insert(operation.id.clone(), operation);
+        Ok(())
+    }
+
+    /// Applies a single logged operation to the bank, as it was originally performed.
+    fn apply_operation(&mut self, operation: &Operation) -> Result<TransactionId> {
+        match &operation.operation_type {
+            OperationType::CreateAccount => self.create_account(&operation.source_account),
+            OperationType::Deposit => self.deposit_with_category(
+                &operation.source_account,
+                operation.amount,
+                operation.category.clone(),
+            ),
+            OperationType::Withdraw => self.withdraw_with_category(
+                &operation.source_account,
+                operation.amount,
+                operation.category.clone(),
+            ),
+            OperationType::Transfer { target_account } => {
+                self.transfer(&operation.source_account, target_account, operation.amount)
+            }
+            OperationType::CloseAccount => self.close_account(&operation.source_account),
+        }
+    }
+
+    /// Creates the account if it does not exist yet, an existing account is left untouched.
+    ///
+    /// # Arguments
+    ///
+    /// * `account` - The code of the account to create.
+    ///
+    /// # Returns
+    /// The id of the operation that created the account.
+    ///
+    /// # Errors
+    /// InvalidAccountNameError
+    /// HistoryCorruptedError if the creation of an existing account is missing from the history.
+    pub fn ensure_account(&mut self, account: &str) -> Result<TransactionId> {
+        normalize_account!(self, account);
+        if !self.accounts.contains_key(account) {
+            return self.create_account(account);
+        }
+        self.get_account_history(account)?
+            .into_iter()
+            .rev()
+            .find(|operation| operation.operation_type == OperationType::CreateAccount)
+            .map(|operation| operation.id.clone())
+            .ok_or_else(|| {
+                error!(
+                    "Creation of account {} is missing from the history",
+                    account
+                );
+                HistoryCorruptedError::MissingAccountCreation {
+                    account: account.to_owned(),
+                }
+                .into()
+            })
+    }
+
+    /// Checks that the operations log is internally consistent without keeping the result.
+    ///
+    /// The log is replayed against a scratch bank, so it is safe to call on untrusted input
+    /// before importing it with [`BankTrait::replay_history`].
+    ///
+    /// # Arguments
+    ///
+    /// * `operations_log` - history of operations to validate
+    ///
+    /// # Errors
+    /// Returns the id of the first operation that can not be applied together with the
+    /// [`BankError`] it produced.
+    pub fn validate_history<'a>(
+        operations_log: impl Iterator<Item = &'a Operation>,
+    ) -> Result<(), (TransactionId, BankError)> {
+        let mut scratch_bank = Bank::new();
+        for operation in operations_log {
+            scratch_bank
+                .apply_operation(operation)
+                .map_err(|err| (operation.id.clone(), err))?;
+        }
+        Ok(())
+    }
+
+    /// Deposits the amount into the account, tagging the operation with the category.
+    ///
+    /// # Errors
+    /// AmountNegativeError
+    /// AccountNotFoundError
+    /// BalanceOverflowError
+    pub fn deposit_with_category(
+        &mut self,
+        account: &str,
+        amount: impl Into<Money>,
+        category: Option<String>,
+    ) -> Result<TransactionId> {
+        normalize_account!(self, account);
+        let amount = amount.into();
+        check_account_exists!(self, account.to_string());
+        check_account_not_frozen!(self, account.to_string());
+
+        if let Some(balance) = self.accounts.get(account) {
+            if amount <= Money::default() {
+                error!("Amount must be positive");
+                Err(AmountNegativeError {
+                    account: account.to_owned(),
+                    amount,
+                }
+                .into())
+            } else {
+                let new_balance = self.credited_balance(account, *balance.borrow(), amount)?;
+                *balance.borrow_mut() = new_balance;
+                let transaction_id = self.get_next_id();
+                let operation = Operation::new(
+                    transaction_id.to_owned(),
+                    account,
+                    amount,
+                    OperationType::Deposit,
+                )
+                .with_category(category);
+                self.push_transaction(operation)?;
+                info!("Deposited into account {}", &account);
+                Ok(transaction_id.to_owned())
+            }
+        } else {
+            Err(BankError::account_not_found(account.to_string()))
+        }
+    }
+
+    /// Returns the balance of the account after crediting the amount.
+    ///
+    /// # Errors
+    /// BalanceOverflowError if the balance would exceed the max balance or overflow
+    fn credited_balance(&self, account: &str, balance: Money, amount: Money) -> Result<Money> {
+        let max_balance = self.max_balance.unwrap_or(Money::MAX);
+        match balance.checked_add(amount) {
+            Some(new_balance) if new_balance <= max_balance => Ok(new_balance),
+            _ => {
+                error!("Balance limit exceeded for account {}", account);
+                Err(BalanceOverflowError {
+                    account: account.to_owned(),
+                    amount,
+                    max_balance,
+                }
+                .into())
+            }
+        }
+    }
+
+    /// Withdraws the amount from the account, tagging the operation with the category.
+    ///
+    /// # Errors
+    /// AmountNegativeError
+    /// AccountNotFoundError
+    /// InsufficientFundsError
+    pub fn withdraw_with_category(
+        &mut self,
+        account: &str,
+        amount: impl Into<Money>,
+        category: Option<String>,
+    ) -> Result<TransactionId> {
+        normalize_account!(self, account);
+        let amount = amount.into();
+        check_account_exists!(self, account.to_string());
+        check_account_not_frozen!(self, account.to_string());
+
+        let transaction_id = self.get_next_id();
+        let operation = Operation::new(
+            transaction_id.to_owned(),
+            account,
+            amount,
+            OperationType::Withdraw,
+        )
+        .with_category(category);
+
+        let overdraft_limit = self.overdraft_limit(account);
+        if let Some(balance) = self.accounts.get_mut(account) {
+            if amount <= Money::default() {
+                error!("Amount must be positive: amount {amount}");
+                return Err(AmountNegativeError {
                     account: account.to_owned(),
                     amount,
                 }
                 .into());
-            } else if *balance < RefCell::from(amount) {
+            } else if !has_sufficient_funds(*balance.get_mut(), amount, overdraft_limit) {
                 let balance = balance.borrow();
                 error!(
                     "Insufficient funds for the operation. Balance: {balance:?} Amount: {amount}"
@@ -292,93 +971,580 @@ impl BankTrait for Bank {
         Ok(transaction_id)
     }
 
-    /// Transfers the specified amount from one account to another.
+    /// Sums the tagged operations by their category.
+    ///
+    /// # Returns
+    ///
+    /// The deposits count as positive and the withdrawals as negative amounts,
+    /// the operations without a category are skipped.
+    pub fn sum_by_category(&self) -> BTreeMap<String, Money> {
+        let mut sums = BTreeMap::new();
+        for operation in self.operations() {
+            let amount = match operation.operation_type {
+                OperationType::Deposit => operation.amount,
+                OperationType::Withdraw => -operation.amount,
+                _ => continue,
+            };
+            if let Some(category) = &operation.category {
+                *sums.entry(category.to_owned()).or_insert(MONEY_ZERO) += amount;
+            }
+        }
+        sums
+    }
+
+    /// Applies a batch of logged operations to the running bank, skipping the ones that fail.
+    ///
+    /// # Arguments
+    ///
+    /// * `operations_log` - operations to import
+    ///
+    /// # Returns
+    /// The number of applied operations and the ids of the skipped ones.
+    pub fn import_operations<'a>(
+        &mut self,
+        operations_log: impl Iterator<Item = &'a Operation>,
+    ) -> (usize, Vec<TransactionId>) {
+        let mut applied = 0;
+        let mut skipped = vec![];
+        for operation in operations_log {
+            match self.apply_operation(operation) {
+                Ok(_) => applied += 1,
+                Err(err) => {
+                    error!("Skipped operation {}: {}", operation.id, err);
+                    skipped.push(operation.id.clone());
+                }
+            }
+        }
+        (applied, skipped)
+    }
+
+    /// Returns the accounts with a negative balance.
+    ///
+    /// # Returns
+    ///
+    /// A vector of account codes with their balances, sorted by the account code.
+    pub fn accounts_in_overdraft(&self) -> Vec<(String, Money)> {
+        let mut overdrafts = self
+            .accounts
+            .iter()
+            .map(|(account, balance)| (account.to_owned(), *balance.borrow()))
+            .filter(|(_, balance)| *balance < MONEY_ZERO)
+            .collect::<Vec<_>>();
+        overdrafts.sort_by(|a, b| a.0.cmp(&b.0));
+        overdrafts
+    }
+
+    /// Returns an iterator over all operations in chronological order without cloning them.
+    pub fn operations(&self) -> impl Iterator<Item = &Operation> {
+        self.history.values()
+    }
+
+    /// Returns the number of operations in the history without copying them.
+    pub fn history_len(&self) -> usize {
+        self.history.len()
+    }
+
+    /// Returns the last `count` operations of the history, oldest first.
+    pub fn latest_operations(&self, count: usize) -> Vec<&Operation> {
+        let mut operations = self.history.values().rev().take(count).collect::<Vec<_>>();
+        operations.reverse();
+        operations
+    }
+
+    /// Captures the balances of the open accounts together with the whole history.
+    pub fn snapshot(&self) -> BankSnapshot {
+        BankSnapshot {
+            balances: self
+                .accounts
+                .iter()
+                .map(|(account, balance)| (account.to_owned(), *balance.borrow()))
+                .collect(),
+            history: self.history.clone(),
+        }
+    }
+
+    /// Creates a bank from the snapshot without replaying the operations.
+    ///
+    /// The account histories are rebuilt from the operations of the snapshot.
+    pub fn restore(snapshot: BankSnapshot) -> Bank {
+        let mut bank = Bank::new();
+        bank.accounts = snapshot
+            .balances
+            .into_iter()
+            .map(|(account, balance)| (account, RefCell::from(balance)))
+            .collect();
+        for operation in snapshot.history.values() {
+            let mut accounts = vec![&operation.source_account];
+            if let OperationType::Transfer { target_account } = &operation.operation_type {
+                accounts.push(target_account);
+            }
+            for account in accounts {
+                bank.accounts_history
+                    .entry(account.to_owned())
+                    .or_default()
+                    .push(operation.id.clone());
+            }
+        }
+        bank.history = snapshot.history;
+        bank
+    }
+
+    /// Removes the operation from the history only, leaving the account references to it.
+    #[cfg(test)]
+    fn drop_history_entry(&mut self, id: &TransactionId) {
+        self.history.remove(id);
+    }
+}
+
+impl Display for Operation {
+    /// Prints the operation as a single human-readable line, e.g. `01H...: deposit 100 to Alice`.
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: ", self.id)?;
+        match &self.operation_type {
+            OperationType::CreateAccount => write!(f, "create account {}", self.source_account)?,
+            OperationType::Deposit => {
+                write!(f, "deposit {} to {}", self.amount, self.source_account)?
+            }
+            OperationType::Withdraw => {
+                write!(f, "withdraw {} from {}", self.amount, self.source_account)?
+            }
+            OperationType::Transfer { target_account } => write!(
+                f,
+                "transfer {} from {} to {}",
+                self.amount, self.source_account, target_account
+            )?,
+            OperationType::CloseAccount => write!(f, "close account {}", self.source_account)?,
+        }
+        if let Some(category) = &self.category {
+            write!(f, " [{}]", category)?;
+        }
+        Ok(())
+    }
+}
+
+/// Makes an independent copy of the accounts, the history and the settings, e.g. to run isolated
+/// load test scenarios against the same state.
+///
+/// The copy gets its own transaction id generator.
+impl Clone for Bank {
+    fn clone(&self) -> Self {
+        Self {
+            accounts: self.accounts.clone(),
+            accounts_history: self.accounts_history.clone(),
+            history: self.history.clone(),
+            ulid_generator: ulid::Generator::new(),
+            account_name_policy: self.account_name_policy.clone(),
+            fee_account: self.fee_account.clone(),
+            idempotency_keys: self.idempotency_keys.clone(),
+            overdraft_limits: self.overdraft_limits.clone(),
+            frozen_accounts: self.frozen_accounts.clone(),
+            max_balance: self.max_balance,
+            normalize_accounts: self.normalize_accounts,
+            #[cfg(test)]
+            fail_next_push: false,
+        }
+    }
+}
+
+impl Display for Bank {
+    /// Prints a compact summary of the bank for debugging and logging.
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Bank: {} accounts, total balance {}, {} operations",
+            self.accounts.len(),
+            self.total_balance(),
+            self.history_len()
+        )
+    }
+}
+
+impl BankTrait for Bank {
+    /// Creates a new account with the specified name and adds it to the bank.
+    ///
+    /// # Arguments
+    ///
+    /// * `account` - The code of the account to create.
+    ///
+    /// # Errors
+    /// AccountDuplicationError
+    /// InvalidAccountNameError
+    ///
+    /// Result
+    /// TransactionId for the new account
+    /// Returns an error if an account with the same name already exists in the bank
+    /// or the name does not follow the account name policy.
+    ///
+    /// ```
+    fn create_account(&mut self, account: &str) -> Result<TransactionId> {
+        normalize_account!(self, account);
+        if let Err(err) = self.account_name_policy.validate(account) {
+            error!("Invalid account name {}", account);
+            return Err(err.into());
+        }
+        if self.accounts.contains_key(account) {
+            error!("Account already exists");
+            return Err(AccountDuplicationError {
+                account: account.to_owned(),
+            }
+            .into());
+        }
+
+        let next_id = self.get_next_id();
+        self.accounts
+            .insert(account.to_owned(), RefCell::from(MONEY_ZERO));
+        let operation = Operation::new(
+            next_id.clone(),
+            account,
+            MONEY_ZERO,
+            OperationType::CreateAccount,
+        );
+        self.push_transaction(operation)?;
+        info!("Created account {}", &account);
+        Ok(next_id)
+    }
+
+    /// Deposits the specified amount into the account.
+    ///
+    /// # Arguments
+    ///
+    /// * `amount` - The amount to deposit into the account.
+    /// * `account` - The account to deposit into.
+    ///
+    /// Result
+    /// `TransactionId` for operation
+    /// # Errors
+    /// AmountNegativeError
+    /// AccountNotFoundError
+    ///
+    /// ```
+    fn deposit(
+        &mut self,
+        account: &str,
+        amount: impl Into<Money>,
+    ) -> Result<TransactionId, BankError> {
+        self.deposit_with_category(account, amount, None)
+    }
+
+    /// Withdraws the specified amount from the account.
+    ///
+    /// # Arguments
+    ///
+    /// * `amount` - The amount to withdraw from the account.
+    /// * `account` - The account to withdraw from.
+    ///
+    /// # Errors
+    /// AmountNegativeError
+    /// AccountNotFoundError
+    /// InsufficientFundsError
+    ///
+    /// Returns an error if the account balance is insufficient to cover the withdrawal amount.
+    ///
+    /// ```
+    fn withdraw(
+        &mut self,
+        account: &str,
+        amount: impl Into<Money>,
+    ) -> Result<TransactionId, BankError> {
+        self.withdraw_with_category(account, amount, None)
+    }
+
+    /// Transfers the specified amount from one account to another.
+    ///
+    /// # Arguments
+    ///
+    /// * `sender` - The name of the account from which the amount will be transferred.
+    /// * `receiver` - The name of the account to which the amount will be transferred.
+    /// * `amount` - The amount to transfer.
+    ///
+    /// # Errors
+    /// AmountNegativeError
+    /// AccountNotFoundError
+    /// InsufficientFundsError
+    /// SomeAccountTransferError
+    /// BalanceOverflowError if the balance of the receiver would exceed the max balance
+    ///
+    /// Returns an error if either the sender or receiver account does not exist, or if
+    /// the sender account does not have sufficient balance to cover the transfer amount.
+    ///
+    /// ```
+    fn transfer(
+        &mut self,
+        sender_account: &str,
+        receiver_account: &str,
+        amount: impl Into<Money>,
+    ) -> Result<TransactionId, BankError> {
+        normalize_account!(self, sender_account, receiver_account);
+        let amount = amount.into();
+        debug!(
+            "transfer {} from {} to {}",
+            amount, sender_account, receiver_account
+        );
+
+        check_account_exists!(self, sender_account.to_string());
+        check_account_exists!(self, receiver_account.to_string());
+        check_account_not_frozen!(self, sender_account.to_string());
+        check_account_not_frozen!(self, receiver_account.to_string());
+
+        if sender_account == receiver_account {
+            error!("Cannot transfer to the same account");
+            return Err(SomeAccountTransferError {
+                account: sender_account.to_owned(),
+            }
+            .into());
+        }
+
+        if let Some(sender_balance) = self.accounts.get(sender_account) {
+            if self.accounts.contains_key(receiver_account) {
+                if amount <= MONEY_ZERO {
+                    error!("Amount must be positive");
+                    Err(AmountNegativeError {
+                        amount,
+                        account: sender_account.to_owned(),
+                    }
+                    .into())
+                } else if !has_sufficient_funds(
+                    *sender_balance.borrow(),
+                    amount,
+                    self.overdraft_limit(sender_account),
+                ) {
+                    let sender_balance = sender_balance.borrow();
+                    error!(
+                        "Insufficient funds for the operation. Balance: {sender_balance:?} Amount: {amount}"
+                    );
+                    Err(InsufficientFundsError {
+                        amount,
+                        account: sender_account.to_owned(),
+                        balance: sender_balance.to_owned(),
+                    }
+                    .into())
+                } else {
+                    let receiver_balance = self.credited_balance(
+                        receiver_account,
+                        *self.accounts[receiver_account].borrow(),
+                        amount,
+                    )?;
+                    let transaction_id = self.get_next_id();
+                    let operation = Operation::new(
+                        transaction_id.to_owned(),
+                        sender_account,
+                        amount,
+                        OperationType::Transfer {
+                            target_account: receiver_account.to_owned(),
+                        },
+                    );
+                    // the balances change only once the operation is recorded
+                    self.push_transaction(operation)?;
+                    *self.accounts[sender_account].borrow_mut() -= amount;
+                    *self.accounts[receiver_account].borrow_mut() = receiver_balance;
+                    info!(
+                        "Transaction id: {} Transferred {} from {} to {}",
+                        transaction_id, amount, sender_account, receiver_account
+                    );
+                    Ok(transaction_id.to_owned())
+                }
+            } else {
+                Err(BankError::account_not_found(receiver_account.to_string()))
+            }
+        } else {
+            Err(BankError::account_not_found(sender_account.to_string()))
+        }
+    }
+
+    /// Performs the transfers atomically, either all of them or none.
+    ///
+    /// # Arguments
+    ///
+    /// * `transfers` - The sender, the receiver and the amount of every transfer, in order.
+    ///
+    /// # Errors
+    /// AmountNegativeError
+    /// AccountNotFoundError
+    /// InsufficientFundsError
+    /// SomeAccountTransferError
+    /// BalanceOverflowError if the balance of a receiver would exceed the max balance
+    ///
+    /// Returns the error of the first transfer that can not be performed, the balances
+    /// and the history stay unchanged then.
+    ///
+    /// ```
+    fn transfer_batch(
+        &mut self,
+        transfers: &[(String, String, Money)],
+    ) -> Result<Vec<TransactionId>> {
+        let transfers = transfers
+            .iter()
+            .map(|(sender_account, receiver_account, amount)| {
+                (
+                    self.account_key(sender_account).into_owned(),
+                    self.account_key(receiver_account).into_owned(),
+                    *amount,
+                )
+            })
+            .collect::<Vec<_>>();
+        let mut balances = HashMap::new();
+        for (sender_account, receiver_account, amount) in &transfers {
+            check_account_exists!(self, sender_account.to_owned());
+            check_account_exists!(self, receiver_account.to_owned());
+            check_account_not_frozen!(self, sender_account.to_owned());
+            check_account_not_frozen!(self, receiver_account.to_owned());
+            if sender_account == receiver_account {
+                error!("Cannot transfer to the same account");
+                return Err(SomeAccountTransferError {
+                    account: sender_account.to_owned(),
+                }
+                .into());
+            }
+            if *amount <= MONEY_ZERO {
+                error!("Amount must be positive");
+                return Err(AmountNegativeError {
+                    amount: *amount,
+                    account: sender_account.to_owned(),
+                }
+                .into());
+            }
+            let sender_balance = *balances
+                .entry(sender_account.as_str())
+                .or_insert(*self.accounts[sender_account].borrow());
+            if !has_sufficient_funds(
+                sender_balance,
+                *amount,
+                self.overdraft_limit(sender_account),
+            ) {
+                error!(
+                    "Insufficient funds for the batch transfer from {}",
+                    sender_account
+                );
+                return Err(InsufficientFundsError {
+                    amount: *amount,
+                    account: sender_account.to_owned(),
+                    balance: sender_balance,
+                }
+                .into());
+            }
+            balances.insert(sender_account.as_str(), sender_balance - *amount);
+            let receiver_balance = *balances
+                .entry(receiver_account.as_str())
+                .or_insert(*self.accounts[receiver_account].borrow());
+            balances.insert(
+                receiver_account.as_str(),
+                self.credited_balance(receiver_account, receiver_balance, *amount)?,
+            );
+        }
+
+        transfers
+            .iter()
+            .map(|(sender_account, receiver_account, amount)| {
+                self.transfer(sender_account, receiver_account, *amount)
+            })
+            .collect()
+    }
+
+    /// Closes the account and removes it from the bank.
+    ///
+    /// # Arguments
+    ///
+    /// * `account` - The code of the account to close.
+    ///
+    /// # Errors
+    /// AccountNotFoundError
+    /// AccountFrozenError
+    /// NonZeroBalanceError
+    ///
+    /// Result
+    /// TransactionId for the closing operation
+    /// The operations of the closed account stay in the history.
+    ///
+    /// ```
+    fn close_account(&mut self, account: &str) -> Result<TransactionId> {
+        normalize_account!(self, account);
+        check_account_exists!(self, account.to_string());
+        check_account_not_frozen!(self, account.to_string());
+        let balance = self.get_balance(account)?;
+        if balance != MONEY_ZERO {
+            error!("Account {} has the balance {}", account, balance);
+            return Err(NonZeroBalanceError {
+                account: account.to_owned(),
+                balance,
+            }
+            .into());
+        }
+
+        let next_id = self.get_next_id();
+        let operation = Operation::new(
+            next_id.clone(),
+            account,
+            MONEY_ZERO,
+            OperationType::CloseAccount,
+        );
+        self.push_transaction(operation)?;
+        self.accounts.remove(account);
+        self.overdraft_limits.remove(account);
+        if self.fee_account.as_deref() == Some(account) {
+            self.fee_account = None;
+        }
+        info!("Closed account {}", &account);
+        Ok(next_id)
+    }
+
+    /// Returns the codes of the open accounts sorted alphabetically.
+    fn list_accounts(&self) -> Vec<String> {
+        let mut accounts = self.accounts.keys().cloned().collect::<Vec<_>>();
+        accounts.sort();
+        accounts
+    }
+
+    /// Returns the number of open accounts.
+    fn account_count(&self) -> usize {
+        self.accounts.len()
+    }
+
+    /// Replays the transaction history stored in a source_bank for the new Bank instance.
     ///
     /// # Arguments
     ///
-    /// * `sender` - The name of the account from which the amount will be transferred.
-    /// * `receiver` - The name of the account to which the amount will be transferred.
-    /// * `amount` - The amount to transfer.
+    /// * `operations_log` - history of operations to replay
     ///
+    /// # Returns
+    /// new instance of Bank
     /// # Errors
-    /// AmountNegativeError
-    /// AccountNotFoundError
-    /// InsufficientFundsError
-    /// SomeAccountTransferError
-    ///
-    /// Returns an error if either the sender or receiver account does not exist, or if
-    /// the sender account does not have sufficient balance to cover the transfer amount.
+    /// Returns an error if the specified account does not exist, if the file does not exist,
+    /// or if there was an error while replaying the transaction history.
     ///
     /// ```
-    fn transfer(
-        &mut self,
-        sender_account: &str,
-        receiver_account: &str,
-        amount: Money,
-    ) -> Result<TransactionId, BankError> {
-        debug!(
-            "transfer {} from {} to {}",
-            amount, sender_account, receiver_account
-        );
-
-        check_account_exists!(self, sender_account.to_string());
-        check_account_exists!(self, receiver_account.to_string());
+    fn replay_history<'a>(operations_log: impl Iterator<Item = &'a Operation>) -> Bank {
+        let mut target_bank = Bank::new();
 
-        if sender_account == receiver_account {
-            error!("Cannot transfer to the same account");
-            return Err(SomeAccountTransferError {
-                account: sender_account.to_owned(),
-            }
-            .into());
+        for operation in operations_log {
+            target_bank.apply_operation(operation).unwrap();
         }
+        target_bank
+    }
+}
 
-        if let Some(sender_balance) = self.accounts.get(sender_account) {
-            if let Some(receiver_balance) = self.accounts.get(receiver_account) {
-                if amount <= MONEY_ZERO {
-                    error!("Amount must be positive");
-                    Err(AmountNegativeError {
-                        amount,
-                        account: sender_account.to_owned(),
-                    }
-                    .into())
-                } else if *sender_balance < RefCell::from(amount) {
-                    let sender_balance = sender_balance.borrow();
-                    error!(
-                        "Insufficient funds for the operation. Balance: {sender_balance:?} Amount: {amount}"
-                    );
-                    Err(InsufficientFundsError {
-                        amount,
-                        account: sender_account.to_owned(),
-                        balance: sender_balance.to_owned(),
-                    }
-                    .into())
-                } else {
-                    *sender_balance.borrow_mut() -= amount;
-                    *receiver_balance.borrow_mut() += amount;
-                    let transaction_id = self.get_next_id();
-                    let operation = Operation {
-                        id: transaction_id.to_owned(),
-                        source_account: sender_account.to_owned(),
-                        amount,
-                        operation_type: OperationType::Transfer {
-                            target_account: receiver_account.to_owned(),
-                        },
-                    };
-                    self.push_transaction(operation)?;
-                    info!(
-                        "Transaction id: {} Transferred {} from {} to {}",
-                        transaction_id, amount, sender_account, receiver_account
-                    );
-                    Ok(transaction_id.to_owned())
-                }
-            } else {
-                Err(BankError::account_not_found(receiver_account.to_string()))
-            }
+impl BankAdmin for Bank {
+    fn set_balance(
+        &mut self,
+        account: &str,
+        balance: impl Into<Money>,
+    ) -> Result<Option<TransactionId>> {
+        let balance = balance.into();
+        let current = self.get_balance(account)?;
+        let category = Some(BALANCE_ADJUSTMENT_CATEGORY.to_string());
+        if balance > current {
+            self.deposit_with_category(account, balance - current, category)
+                .map(Some)
+        } else if balance < current {
+            self.withdraw_with_category(account, current - balance, category)
+                .map(Some)
         } else {
-            Err(BankError::account_not_found(sender_account.to_string()))
+            info!("Balance of account {} is already {}", account, balance);
+            Ok(None)
         }
     }
+}
 
+impl BankView for Bank {
     /// Returns the current balance of the account.
     /// # Arguments
     ///
@@ -390,6 +1556,7 @@ impl BankTrait for Bank {
     /// AccountNotFoundError
     /// ```
     fn get_balance(&self, account: &str) -> Result<Money, BankError> {
+        normalize_account!(self, account);
         debug!("get_balance {}", account);
         check_account_exists!(self, account.to_string());
         Ok(self
@@ -428,54 +1595,91 @@ impl BankTrait for Bank {
     ///
     /// # Errors
     /// BankError
+    /// HistoryCorruptedError if an operation of the account is missing from the history.
+    ///
+    /// The history of a closed account stays available.
     /// ```
     fn get_account_history(&self, account: &str) -> Result<Vec<&Operation>, BankError> {
-        check_account_exists!(self, account.to_string());
-        let transaction_history = self.accounts_history.get(account);
-        let transaction_history = transaction_history.unwrap();
-        Ok(transaction_history
-            .iter()
-            .map(|t| self.history.get(t).unwrap())
-            .collect())
+        normalize_account!(self, account);
+        if !self.accounts_history.contains_key(account) {
+            check_account_exists!(self, account.to_string());
+        }
+        self.accounts_history
+            .get(account)
+            .into_iter()
+            .flatten()
+            .map(|t| {
+                self.history.get(t).ok_or_else(|| {
+                    error!("Operation {} is missing from the history", t);
+                    HistoryCorruptedError::MissingOperation {
+                        transaction_id: t.to_owned(),
+                    }
+                    .into()
+                })
+            })
+            .collect()
     }
 
-    /// Replays the transaction history stored in a source_bank for the new Bank instance.
+    /// Returns an `Option<&Operation>` representing the operation with the given ID if it exists in the history,
     ///
     /// # Arguments
     ///
-    /// * `operations_log` - history of operations to replay
+    /// * `id` - The ID of the operation to retrieve.
     ///
     /// # Returns
-    /// new instance of Bank
+    ///
+    /// Returns an `Option<&Operation>` representing the operation with the given ID if it exists in the history,
+    /// or `None` if no operation with the given ID is found.
+    ///
+    fn get_operation_by_id(&self, id: &TransactionId) -> Option<&Operation> {
+        self.history.get(id)
+    }
+}
+
+/// The read-only queries of a bank, for the consumers that never change it.
+pub trait BankView {
+    /// Returns the current balance of the account.
+    /// # Arguments
+    ///
+    ///  * 'account' - The code of the account for which to retrieve the balance.
+    ///
+    /// # Returns
+    /// The current balance of the account.
+    /// # Errors
+    /// AccountNotFoundError
+    /// ```
+    fn get_balance(&self, account: &str) -> Result<Money, BankError>;
+
+    /// Returns the transaction history for the bank.
+    ///
+    /// # Arguments
+    /// self
+    /// # Returns
+    ///
+    /// A vector of strings representing the transaction history of the account.
+    ///
     /// # Errors
-    /// Returns an error if the specified account does not exist, if the file does not exist,
-    /// or if there was an error while replaying the transaction history.
     ///
+    /// Returns an error if the specified account does not exist.
     /// ```
-    fn replay_history<'a>(operations_log: impl Iterator<Item = &'a Operation>) -> Bank {
-        let mut target_bank = Bank::new();
+    fn get_history(&self) -> Result<Vec<Operation>>;
 
-        for operation in operations_log {
-            match &operation.operation_type {
-                OperationType::CreateAccount => target_bank
-                    .create_account(&operation.source_account)
-                    .unwrap(),
-                OperationType::Deposit => target_bank
-                    .deposit(&operation.source_account, operation.amount)
-                    .unwrap(),
-                OperationType::Withdraw => target_bank
-                    .withdraw(&operation.source_account, operation.amount)
-                    .unwrap(),
-                OperationType::Transfer { target_account } => target_bank
-                    .transfer(&operation.source_account, target_account, operation.amount)
-                    .unwrap(),
-            };
-            //}
-        }
-        target_bank
-    }
+    /// Returns the transaction history of the specified account.
+    ///
+    /// # Arguments
+    ///
+    /// * `account` - The name of the account for which to retrieve the transaction history.
+    ///
+    /// # Returns
+    ///
+    /// A vector of strings representing the transaction history of the account.
+    ///
+    /// # Errors
+    /// BankError
+    /// ```
+    fn get_account_history(&self, account: &str) -> Result<Vec<&Operation>>;
 
-    /// Returns an `Option<&Operation>` representing the operation with the given ID if it exists in the history,
+    // Retrieves an operation from the history by its ID.
     ///
     /// # Arguments
     ///
@@ -486,12 +1690,10 @@ impl BankTrait for Bank {
     /// Returns an `Option<&Operation>` representing the operation with the given ID if it exists in the history,
     /// or `None` if no operation with the given ID is found.
     ///
-    fn get_operation_by_id(&self, id: &TransactionId) -> Option<&Operation> {
-        self.history.get(id)
-    }
+    fn get_operation_by_id(&self, id: &TransactionId) -> Option<&Operation>;
 }
 
-pub trait BankTrait {
+pub trait BankTrait: BankView {
     /// Creates a new account with the specified name and adds it to the bank.
     ///
     /// # Arguments
@@ -521,7 +1723,7 @@ pub trait BankTrait {
     /// AccountNotFoundError
     ///
     /// ```
-    fn deposit(&mut self, account: &str, amount: Money) -> Result<TransactionId>;
+    fn deposit(&mut self, account: &str, amount: impl Into<Money>) -> Result<TransactionId>;
 
     /// Withdraws the specified amount from the account.
     ///
@@ -539,7 +1741,7 @@ pub trait BankTrait {
     /// InsufficientFundsError
     ///
     /// ```
-    fn withdraw(&mut self, account: &str, amount: Money) -> Result<TransactionId>;
+    fn withdraw(&mut self, account: &str, amount: impl Into<Money>) -> Result<TransactionId>;
 
     /// Transfers the specified amount from one account to another.
     ///
@@ -564,49 +1766,54 @@ pub trait BankTrait {
         &mut self,
         sender_account: &str,
         receiver_account: &str,
-        amount: Money,
+        amount: impl Into<Money>,
     ) -> Result<TransactionId>;
 
-    /// Returns the current balance of the account.
+    /// Performs the transfers atomically, either all of them or none.
+    ///
     /// # Arguments
     ///
-    ///  * 'account' - The code of the account for which to retrieve the balance.
+    /// * `transfers` - The sender, the receiver and the amount of every transfer, in order.
     ///
     /// # Returns
-    /// The current balance of the account.
+    /// [TransactionId] of every transfer, in order
+    ///
     /// # Errors
+    /// AmountNegativeError
     /// AccountNotFoundError
+    /// InsufficientFundsError
+    /// SomeAccountTransferError
+    ///
     /// ```
-    fn get_balance(&self, account: &str) -> Result<Money, BankError>;
+    fn transfer_batch(
+        &mut self,
+        transfers: &[(String, String, Money)],
+    ) -> Result<Vec<TransactionId>>;
 
-    /// Returns the transaction history for the bank.
+    /// Closes the account with a zero balance and removes it from the bank.
     ///
     /// # Arguments
-    /// self
-    /// # Returns
     ///
-    /// A vector of strings representing the transaction history of the account.
+    /// * `account` - The code of the account to close.
+    ///
+    /// # Returns
+    /// [TransactionId] of the closing operation
     ///
     /// # Errors
+    /// AccountNotFoundError
+    /// NonZeroBalanceError
     ///
-    /// Returns an error if the specified account does not exist.
     /// ```
-    fn get_history(&self) -> Result<Vec<Operation>>;
+    fn close_account(&mut self, account: &str) -> Result<TransactionId>;
 
-    /// Returns the transaction history of the specified account.
-    ///
-    /// # Arguments
-    ///
-    /// * `account` - The name of the account for which to retrieve the transaction history.
+    /// Returns the codes of the open accounts.
     ///
     /// # Returns
-    ///
-    /// A vector of strings representing the transaction history of the account.
-    ///
-    /// # Errors
-    /// BankError
-    /// ```
-    fn get_account_history(&self, account: &str) -> Result<Vec<&Operation>>;
+    /// The account codes sorted alphabetically.
+    fn list_accounts(&self) -> Vec<String>;
+
+    /// Returns the number of open accounts.
+    fn account_count(&self) -> usize;
 
     /// Replays the transaction history stored in a source_bank for the new Bank instance.
     ///
@@ -623,19 +1830,32 @@ pub trait BankTrait {
     ///
     /// ```
     fn replay_history<'a>(operations_log: impl Iterator<Item = &'a Operation>) -> Bank;
+}
 
-    // Retrieves an operation from the history by its ID.
-    ///
-    /// # Arguments
+/// The administrative operations of a bank, e.g. for test fixtures or corrections.
+///
+/// They set the state directly instead of following a client request,
+/// so the trait has to be imported explicitly to use them.
+pub trait BankAdmin {
+    /// Sets the exact balance of the account.
     ///
-    /// * `id` - The ID of the operation to retrieve.
+    /// The difference is recorded as a deposit or a withdrawal tagged with
+    /// [`BALANCE_ADJUSTMENT_CATEGORY`], so the history stays consistent with the balance.
     ///
     /// # Returns
+    /// The id of the adjusting operation, `None` when the balance is already set
+    /// and nothing is recorded.
     ///
-    /// Returns an `Option<&Operation>` representing the operation with the given ID if it exists in the history,
-    /// or `None` if no operation with the given ID is found.
-    ///
-    fn get_operation_by_id(&self, id: &TransactionId) -> Option<&Operation>;
+    /// # Errors
+    /// AccountNotFoundError
+    /// AccountFrozenError
+    /// InsufficientFundsError if a negative balance exceeds the overdraft limit
+    /// BalanceOverflowError
+    fn set_balance(
+        &mut self,
+        account: &str,
+        balance: impl Into<Money>,
+    ) -> Result<Option<TransactionId>>;
 }
 
 #[test_env_helpers::before_all]
@@ -680,6 +1900,29 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_create_account_name_policy() {
+        let mut bank = Bank::new().with_account_name_policy(AccountNamePolicy {
+            max_length: 8,
+            allowed_chars: |c| c.is_ascii_alphanumeric(),
+        });
+
+        assert_eq!(
+            bank.create_account(""),
+            Err(BankError::InvalidAccountName(InvalidAccountNameError {
+                account: "".to_string()
+            }))
+        );
+        assert_eq!(
+            bank.create_account("Bartholomew"),
+            Err(BankError::InvalidAccountName(InvalidAccountNameError {
+                account: "Bartholomew".to_string()
+            }))
+        );
+        assert!(bank.create_account("Alice").is_ok());
+        assert!(Bank::new().create_account("").is_err());
+    }
+
     #[test]
     fn test_deposit() {
         let mut bank = bank_with_accounts!("Alice");
@@ -699,7 +1942,7 @@ mod tests {
                 res,
                 BankError::AmountNegative(AmountNegativeError {
                     account: "Alice".to_string(),
-                    amount: -50.0,
+                    amount: Money::from(-50.0),
                 })
             ),
         }
@@ -726,7 +1969,7 @@ mod tests {
                 res,
                 BankError::AmountNegative(AmountNegativeError {
                     account: "Alice".to_string(),
-                    amount: -30.0,
+                    amount: Money::from(-30.0),
                 })
             ),
         }
@@ -737,8 +1980,8 @@ mod tests {
                 res,
                 BankError::InsufficientFunds(InsufficientFundsError {
                     account: "Alice".to_string(),
-                    balance: 50.0,
-                    amount: 100.0,
+                    balance: Money::from(50.0),
+                    amount: Money::from(100.0),
                 })
             ),
         }
@@ -759,7 +2002,7 @@ mod tests {
             assert_eq!(
                 res,
                 AmountNegativeError {
-                    amount: -30.0,
+                    amount: Money::from(-30.0),
                     account: "Alice".to_string(),
                 }
                 .into()
@@ -772,9 +2015,9 @@ mod tests {
             assert_eq!(
                 res,
                 InsufficientFundsError {
-                    amount: 100.0,
+                    amount: Money::from(100.0),
                     account: "Alice".to_string(),
-                    balance: 50.0,
+                    balance: Money::from(50.0),
                 }
                 .into()
             );
@@ -819,6 +2062,374 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_transfer_is_atomic() {
+        let mut bank = bank_with_accounts!("Alice", "Bob");
+        bank.deposit("Alice", 100.0).unwrap();
+        let history_len = bank.history_len();
+
+        bank.fail_next_push = true;
+        assert!(bank.transfer("Alice", "Bob", 50.0).is_err());
+        assert_eq!(bank.get_balance("Alice"), Ok(Money::from(100.0)));
+        assert_eq!(bank.get_balance("Bob"), Ok(MONEY_ZERO));
+        assert_eq!(bank.history_len(), history_len);
+    }
+
+    #[test]
+    fn test_fee_account() {
+        let mut bank = bank_with_accounts!("Alice", "Bob", "Fees", "Treasury");
+        bank.deposit("Alice", 100.0).unwrap();
+        assert!(bank
+            .set_fee_account("Missing")
+            .unwrap_err()
+            .is_account_not_found());
+
+        bank.set_fee_account("Fees").unwrap();
+        bank.transfer_with_fee("Alice", "Bob", 50.0, 1.0).unwrap();
+        assert_eq!(bank.get_balance("Fees"), Ok(Money::from(1.0)));
+
+        bank.set_fee_account("Treasury").unwrap();
+        assert_eq!(bank.fee_account(), Some("Treasury"));
+        bank.transfer_with_fee("Alice", "Bob", 10.0, 2.0).unwrap();
+        assert_eq!(bank.get_balance("Fees"), Ok(Money::from(1.0)));
+        assert_eq!(bank.get_balance("Treasury"), Ok(Money::from(2.0)));
+        assert_eq!(bank.get_balance("Alice"), Ok(Money::from(37.0)));
+        assert_eq!(bank.get_balance("Bob"), Ok(Money::from(60.0)));
+
+        assert!(bank
+            .transfer_with_fee("Alice", "Bob", 37.0, 1.0)
+            .unwrap_err()
+            .is_insufficient_funds());
+    }
+
+    #[test]
+    fn test_fee_account_frozen() {
+        let mut bank = bank_with_accounts!("Alice", "Bob", "Fees");
+        bank.deposit("Alice", 100.0).unwrap();
+        bank.set_fee_account("Fees").unwrap();
+        bank.freeze_account("Fees").unwrap();
+        let history_len = bank.history_len();
+
+        assert!(bank
+            .transfer_with_fee("Alice", "Bob", 50.0, 1.0)
+            .unwrap_err()
+            .is_account_frozen());
+        assert_eq!(bank.get_balance("Alice"), Ok(Money::from(100.0)));
+        assert_eq!(bank.get_balance("Bob"), Ok(MONEY_ZERO));
+        assert_eq!(bank.get_balance("Fees"), Ok(MONEY_ZERO));
+        assert_eq!(bank.history_len(), history_len);
+    }
+
+    #[test]
+    fn test_transfer_with_fee_overflow() {
+        let mut bank = bank_with_accounts!("Alice", "Bob", "Fees");
+        bank.deposit("Alice", 100.0).unwrap();
+        bank.set_fee_account("Fees").unwrap();
+
+        assert_eq!(
+            bank.transfer_with_fee("Alice", "Bob", 1e300, 1.0),
+            Err(InsufficientFundsError {
+                account: "Alice".to_string(),
+                amount: Money::MAX,
+                balance: Money::from(100.0),
+            }
+            .into())
+        );
+        assert_eq!(bank.get_balance("Alice"), Ok(Money::from(100.0)));
+        assert_eq!(bank.get_balance("Bob"), Ok(MONEY_ZERO));
+        assert_eq!(bank.get_balance("Fees"), Ok(MONEY_ZERO));
+    }
+
+    #[test]
+    fn test_transfer_batch() {
+        let mut bank = bank_with_accounts!("Employer", "Alice", "Bob");
+        bank.deposit("Employer", 100.0).unwrap();
+        let payroll = [
+            (
+                "Employer".to_string(),
+                "Alice".to_string(),
+                Money::from(60.0),
+            ),
+            ("Employer".to_string(), "Bob".to_string(), Money::from(40.0)),
+            ("Bob".to_string(), "Alice".to_string(), Money::from(15.0)),
+        ];
+
+        let ids = bank.transfer_batch(&payroll).unwrap();
+        assert_eq!(ids.len(), 3);
+        assert_eq!(
+            bank.get_operation_by_id(&ids[2]).unwrap().source_account,
+            "Bob"
+        );
+        assert_eq!(bank.get_balance("Employer"), Ok(MONEY_ZERO));
+        assert_eq!(bank.get_balance("Alice"), Ok(Money::from(75.0)));
+        assert_eq!(bank.get_balance("Bob"), Ok(Money::from(25.0)));
+    }
+
+    #[test]
+    fn test_transfer_batch_rollback() {
+        let mut bank = bank_with_accounts!("Employer", "Alice", "Bob");
+        bank.deposit("Employer", 100.0).unwrap();
+        let history_len = bank.history_len();
+        let payroll = [
+            (
+                "Employer".to_string(),
+                "Alice".to_string(),
+                Money::from(60.0),
+            ),
+            ("Employer".to_string(), "Bob".to_string(), Money::from(50.0)),
+            ("Bob".to_string(), "Carol".to_string(), Money::from(10.0)),
+        ];
+
+        assert_eq!(
+            bank.transfer_batch(&payroll),
+            Err(InsufficientFundsError {
+                account: "Employer".to_string(),
+                amount: Money::from(50.0),
+                balance: Money::from(40.0),
+            }
+            .into())
+        );
+        assert_eq!(bank.get_balance("Employer"), Ok(Money::from(100.0)));
+        assert_eq!(bank.get_balance("Alice"), Ok(MONEY_ZERO));
+        assert_eq!(bank.history_len(), history_len);
+
+        let self_transfer = [("Alice".to_string(), "Alice".to_string(), Money::from(1.0))];
+        assert!(bank
+            .transfer_batch(&self_transfer)
+            .unwrap_err()
+            .is_some_account_transfer());
+    }
+
+    #[test]
+    fn test_has_sufficient_funds() {
+        let (balance, amount) = (Money::from(100.0), Money::from(100.0));
+        assert!(has_sufficient_funds(balance, amount, MONEY_ZERO));
+        assert!(!has_sufficient_funds(
+            balance,
+            Money::from(100.01),
+            MONEY_ZERO
+        ));
+        assert!(has_sufficient_funds(
+            balance,
+            Money::from(150.0),
+            Money::from(50.0)
+        ));
+        assert!(!has_sufficient_funds(
+            balance,
+            Money::from(150.01),
+            Money::from(50.0)
+        ));
+        assert!(has_sufficient_funds(
+            Money::from(-20.0),
+            Money::from(10.0),
+            Money::from(30.0)
+        ));
+        assert!(has_sufficient_funds(balance, Money::MAX, Money::MAX));
+    }
+
+    #[test]
+    fn test_withdraw_with_huge_overdraft_limit() {
+        let mut bank = bank_with_accounts!("Alice", "Bob");
+        bank.deposit("Alice", 100.0).unwrap();
+        bank.set_overdraft_limit("Alice", 1e300).unwrap();
+
+        bank.withdraw("Alice", 1.0).unwrap();
+        assert_eq!(bank.get_balance("Alice"), Ok(Money::from(99.0)));
+        bank.transfer("Alice", "Bob", 1.0).unwrap();
+        assert_eq!(bank.get_balance("Alice"), Ok(Money::from(98.0)));
+    }
+
+    #[test]
+    fn test_overdraft_limit() {
+        let mut bank = bank_with_accounts!("Alice", "Bob");
+        bank.deposit("Alice", 100.0).unwrap();
+        assert!(bank
+            .set_overdraft_limit("Alice", -1.0)
+            .unwrap_err()
+            .is_amount_negative());
+        bank.set_overdraft_limit("Alice", 50.0).unwrap();
+
+        bank.withdraw("Alice", 120.0).unwrap();
+        assert_eq!(bank.get_balance("Alice"), Ok(Money::from(-20.0)));
+
+        bank.transfer("Alice", "Bob", 30.0).unwrap();
+        assert_eq!(bank.get_balance("Alice"), Ok(Money::from(-50.0)));
+
+        assert_eq!(
+            bank.withdraw("Alice", 0.01),
+            Err(InsufficientFundsError {
+                account: "Alice".to_string(),
+                amount: Money::from(0.01),
+                balance: Money::from(-50.0),
+            }
+            .into())
+        );
+        assert!(bank
+            .transfer("Alice", "Bob", 1.0)
+            .unwrap_err()
+            .is_insufficient_funds());
+        assert!(bank
+            .withdraw("Bob", 31.0)
+            .unwrap_err()
+            .is_insufficient_funds());
+    }
+
+    #[test]
+    fn test_get_balance_at() {
+        let mut bank = bank_with_accounts!("Alice", "Bob");
+        let deposit_id = bank.deposit("Alice", 100.0).unwrap();
+        let withdraw_id = bank.withdraw("Alice", 30.0).unwrap();
+        let transfer_id = bank.transfer("Alice", "Bob", 20.0).unwrap();
+        bank.deposit("Alice", 5.0).unwrap();
+
+        assert_eq!(
+            bank.get_balance_at("Alice", &deposit_id),
+            Ok(Money::from(100.0))
+        );
+        assert_eq!(
+            bank.get_balance_at("Alice", &withdraw_id),
+            Ok(Money::from(70.0))
+        );
+        assert_eq!(
+            bank.get_balance_at("Alice", &transfer_id),
+            Ok(Money::from(50.0))
+        );
+        assert_eq!(bank.get_balance_at("Bob", &withdraw_id), Ok(MONEY_ZERO));
+        assert_eq!(
+            bank.get_balance_at("Bob", &transfer_id),
+            Ok(Money::from(20.0))
+        );
+
+        assert!(bank
+            .get_balance_at("Carol", &deposit_id)
+            .unwrap_err()
+            .is_account_not_found());
+        assert!(bank
+            .get_balance_at("Alice", &"missing".to_string())
+            .unwrap_err()
+            .is_operation_not_found());
+    }
+
+    #[test]
+    fn test_close_empty_accounts() {
+        let mut bank = bank_with_accounts!("Alice", "Bob", "Carol", "Dave");
+        bank.deposit("Alice", 100.0).unwrap();
+        bank.deposit("Carol", 50.0).unwrap();
+        bank.withdraw("Carol", 50.0).unwrap();
+
+        assert_eq!(bank.close_empty_accounts(), vec!["Bob", "Carol", "Dave"]);
+        assert_eq!(bank.list_accounts(), vec!["Alice"]);
+        assert_eq!(bank.get_balance("Alice"), Ok(Money::from(100.0)));
+        assert_eq!(
+            bank.get_account_history("Bob")
+                .unwrap()
+                .last()
+                .unwrap()
+                .operation_type,
+            OperationType::CloseAccount
+        );
+        assert!(bank.close_empty_accounts().is_empty());
+    }
+
+    #[test]
+    fn test_account_statement() {
+        let mut bank = bank_with_accounts!("Alice", "Bob");
+        bank.deposit("Alice", 100.0).unwrap();
+        bank.withdraw("Alice", 30.0).unwrap();
+        bank.transfer("Bob", "Alice", 0.0).unwrap_err();
+        bank.deposit("Bob", 5.0).unwrap();
+        bank.transfer("Bob", "Alice", 5.0).unwrap();
+
+        let statement = bank.account_statement("Alice").unwrap();
+        let steps = statement
+            .iter()
+            .map(|(operation, balance)| (operation.operation_type.clone(), *balance))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            steps,
+            vec![
+                (OperationType::CreateAccount, MONEY_ZERO),
+                (OperationType::Deposit, Money::from(100.0)),
+                (OperationType::Withdraw, Money::from(70.0)),
+                (
+                    OperationType::Transfer {
+                        target_account: "Alice".to_string()
+                    },
+                    Money::from(75.0)
+                ),
+            ]
+        );
+        assert!(bank
+            .account_statement("Carol")
+            .unwrap_err()
+            .is_account_not_found());
+    }
+
+    #[test]
+    fn test_signed_amount_for() {
+        let mut bank = bank_with_accounts!("Alice", "Bob");
+        let deposit_id = bank.deposit("Alice", 100.0).unwrap();
+        let withdraw_id = bank.withdraw("Alice", 30.0).unwrap();
+        let transfer_id = bank.transfer("Alice", "Bob", 20.0).unwrap();
+
+        let deposit = bank.get_operation_by_id(&deposit_id).unwrap();
+        assert_eq!(bank.signed_amount_for(deposit, "Alice"), Money::from(100.0));
+        assert_eq!(bank.signed_amount_for(deposit, "Bob"), MONEY_ZERO);
+
+        let withdraw = bank.get_operation_by_id(&withdraw_id).unwrap();
+        assert_eq!(
+            bank.signed_amount_for(withdraw, "Alice"),
+            Money::from(-30.0)
+        );
+
+        let transfer = bank.get_operation_by_id(&transfer_id).unwrap();
+        assert_eq!(
+            bank.signed_amount_for(transfer, "Alice"),
+            Money::from(-20.0)
+        );
+        assert_eq!(bank.signed_amount_for(transfer, "Bob"), Money::from(20.0));
+    }
+
+    #[test]
+    fn test_transfer_split() {
+        let mut bank = bank_with_accounts!("Employer", "Alice", "Bob", "Carol");
+        bank.deposit("Employer", 100.0).unwrap();
+
+        let ids = bank
+            .transfer_split(
+                "Employer",
+                &[
+                    ("Alice", Money::from(50.0)),
+                    ("Bob", Money::from(30.0)),
+                    ("Carol", Money::from(20.0)),
+                ],
+            )
+            .unwrap();
+        assert_eq!(ids.len(), 3);
+        assert_eq!(bank.get_balance("Employer"), Ok(MONEY_ZERO));
+        assert_eq!(bank.get_balance("Alice"), Ok(Money::from(50.0)));
+        assert_eq!(bank.get_balance("Bob"), Ok(Money::from(30.0)));
+        assert_eq!(bank.get_balance("Carol"), Ok(Money::from(20.0)));
+    }
+
+    #[test]
+    fn test_transfer_split_exceeds_balance() {
+        let mut bank = bank_with_accounts!("Employer", "Alice", "Bob");
+        bank.deposit("Employer", 100.0).unwrap();
+        let history_len = bank.history_len();
+
+        assert!(bank
+            .transfer_split(
+                "Employer",
+                &[("Alice", Money::from(60.0)), ("Bob", Money::from(60.0))],
+            )
+            .unwrap_err()
+            .is_insufficient_funds());
+        assert_eq!(bank.get_balance("Employer"), Ok(Money::from(100.0)));
+        assert_eq!(bank.get_balance("Alice"), Ok(MONEY_ZERO));
+        assert_eq!(bank.history_len(), history_len);
+    }
+
     #[test]
     fn test_transfer_without_target() {
         let mut bank = bank_with_accounts!("Alice");
@@ -835,6 +2446,21 @@ mod tests {
         )
     }
 
+    #[test]
+    fn test_bank_view() {
+        fn total<V: BankView>(view: &V, accounts: &[&str]) -> Money {
+            accounts
+                .iter()
+                .map(|account| view.get_balance(account).unwrap())
+                .sum()
+        }
+
+        let mut bank = bank_with_accounts!("Alice", "Bob");
+        bank.deposit("Alice", 30.0).unwrap();
+        bank.deposit("Bob", 12.5).unwrap();
+        assert_eq!(total(&bank, &["Alice", "Bob"]), Money::from(42.5));
+    }
+
     #[test]
     fn test_get_balance() {
         let mut bank = bank_with_accounts!("Alice");
@@ -859,6 +2485,389 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_close_account() {
+        let mut bank = bank_with_accounts!("Alice", "Bob");
+        bank.deposit("Alice", 100.0).unwrap();
+
+        assert_eq!(
+            bank.close_account("Alice"),
+            Err(NonZeroBalanceError {
+                account: "Alice".to_string(),
+                balance: Money::from(100.0),
+            }
+            .into())
+        );
+        assert_eq!(
+            bank.close_account("Carol"),
+            Err(BankError::account_not_found("Carol".to_string()))
+        );
+
+        bank.transfer("Alice", "Bob", 100.0).unwrap();
+        let id = bank.close_account("Alice").unwrap();
+        assert_eq!(
+            bank.get_balance("Alice"),
+            Err(BankError::account_not_found("Alice".to_string()))
+        );
+        assert_eq!(
+            bank.get_operation_by_id(&id).unwrap().operation_type,
+            OperationType::CloseAccount
+        );
+        let alice_history = bank.get_account_history("Alice").unwrap();
+        assert_eq!(alice_history.len(), 4);
+        assert_eq!(bank.get_history().unwrap().len(), 5);
+    }
+
+    #[test]
+    fn test_account_normalization() {
+        let mut bank = Bank::new().with_account_normalization();
+        bank.create_account("Alice").unwrap();
+        assert!(bank
+            .create_account(" alice ")
+            .unwrap_err()
+            .is_account_duplication());
+        bank.deposit(" alice ", 10.0).unwrap();
+        bank.create_account("BOB").unwrap();
+        bank.transfer("ALICE", "bob", 4.0).unwrap();
+        assert_eq!(bank.get_balance("Alice"), Ok(Money::from(6.0)));
+        assert_eq!(bank.get_balance(" Bob"), Ok(Money::from(4.0)));
+        assert_eq!(bank.list_accounts(), vec!["alice", "bob"]);
+        assert_eq!(bank.get_account_history("aLiCe").unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_account_normalization_off() {
+        let mut bank = bank_with_accounts!("Alice");
+        bank.create_account(" alice ").unwrap();
+        bank.deposit("Alice", 10.0).unwrap();
+        assert_eq!(bank.get_balance("Alice"), Ok(Money::from(10.0)));
+        assert_eq!(bank.get_balance(" alice "), Ok(MONEY_ZERO));
+        assert!(bank
+            .get_balance("alice")
+            .unwrap_err()
+            .is_account_not_found());
+    }
+
+    #[test]
+    fn test_set_balance() {
+        let mut bank = bank_with_accounts!("Alice");
+        bank.deposit("Alice", 10.0).unwrap();
+
+        let id = bank.set_balance("Alice", 25.0).unwrap().unwrap();
+        assert_eq!(bank.get_balance("Alice"), Ok(Money::from(25.0)));
+        let operation = bank.get_operation_by_id(&id).unwrap();
+        assert_eq!(operation.operation_type, OperationType::Deposit);
+        assert_eq!(operation.amount, Money::from(15.0));
+        assert_eq!(
+            operation.category.as_deref(),
+            Some(BALANCE_ADJUSTMENT_CATEGORY)
+        );
+
+        let id = bank.set_balance("Alice", 5.0).unwrap().unwrap();
+        assert_eq!(bank.get_balance("Alice"), Ok(Money::from(5.0)));
+        let operation = bank.get_operation_by_id(&id).unwrap();
+        assert_eq!(operation.operation_type, OperationType::Withdraw);
+        assert_eq!(operation.amount, Money::from(20.0));
+
+        let history_len = bank.history_len();
+        assert_eq!(bank.set_balance("Alice", 5.0), Ok(None));
+        assert_eq!(bank.history_len(), history_len);
+
+        assert!(bank
+            .set_balance("Alice", -1.0)
+            .unwrap_err()
+            .is_insufficient_funds());
+        assert!(bank
+            .set_balance("Carol", 1.0)
+            .unwrap_err()
+            .is_account_not_found());
+    }
+
+    #[test]
+    fn test_ensure_account() {
+        let mut bank = Bank::new();
+        let created_id = bank.ensure_account("Alice").unwrap();
+        bank.deposit("Alice", 100.0).unwrap();
+
+        assert_eq!(bank.ensure_account("Alice"), Ok(created_id.clone()));
+        assert_eq!(bank.get_balance("Alice"), Ok(Money::from(100.0)));
+        assert_eq!(bank.history_len(), 2);
+
+        bank.accounts_history
+            .get_mut("Alice")
+            .unwrap()
+            .retain(|id| *id != created_id);
+        assert_eq!(
+            bank.ensure_account("Alice"),
+            Err(BankError::HistoryCorrupted(
+                HistoryCorruptedError::MissingAccountCreation {
+                    account: "Alice".to_string()
+                }
+            ))
+        );
+    }
+
+    #[test]
+    fn test_list_accounts() {
+        let mut bank = bank_with_accounts!("Carol", "Alice", "Bob");
+        assert_eq!(bank.list_accounts(), vec!["Alice", "Bob", "Carol"]);
+        assert_eq!(bank.account_count(), 3);
+
+        bank.close_account("Bob").unwrap();
+        assert_eq!(bank.list_accounts(), vec!["Alice", "Carol"]);
+        assert_eq!(bank.account_count(), 2);
+    }
+
+    #[test]
+    fn test_deposit_is_exact() {
+        let mut bank = bank_with_accounts!("Alice");
+        for _ in 0..10 {
+            bank.deposit("Alice", 0.1).unwrap();
+        }
+        assert_eq!(bank.get_balance("Alice"), Ok(Money::from(1.0)));
+        assert_eq!(bank.get_balance("Alice").unwrap().to_f64(), 1.0);
+    }
+
+    #[test]
+    fn test_snapshot_round_trip() {
+        let mut bank = bank_with_accounts!("Alice", "Bob", "Carol");
+        bank.deposit("Alice", 100.0).unwrap();
+        bank.transfer("Alice", "Bob", 25.5).unwrap();
+        bank.close_account("Carol").unwrap();
+
+        let json = serde_json::to_string(&bank.snapshot()).unwrap();
+        let restored = Bank::restore(serde_json::from_str(&json).unwrap());
+
+        assert_eq!(restored.list_accounts(), bank.list_accounts());
+        for account in bank.list_accounts() {
+            assert_eq!(restored.get_balance(&account), bank.get_balance(&account));
+            assert_eq!(
+                restored.get_account_history(&account),
+                bank.get_account_history(&account)
+            );
+        }
+        assert_eq!(restored.get_history(), bank.get_history());
+        assert_eq!(
+            restored.get_account_history("Carol"),
+            bank.get_account_history("Carol")
+        );
+    }
+
+    #[test]
+    fn test_error_predicates() {
+        let mut bank = bank_with_accounts!("Alice", "Bob");
+        bank.deposit("Alice", 10.0).unwrap();
+        let errors = [
+            bank.create_account("Alice").unwrap_err(),
+            bank.deposit("Alice", -1.0).unwrap_err(),
+            bank.deposit("Carol", 1.0).unwrap_err(),
+            bank.withdraw("Alice", 100.0).unwrap_err(),
+            bank.transfer("Alice", "Alice", 1.0).unwrap_err(),
+            bank.create_account("").unwrap_err(),
+            BankError::from(HistoryCorruptedError::MissingOperation {
+                transaction_id: "1".to_string(),
+            }),
+            bank.close_account("Alice").unwrap_err(),
+            bank.get_balance_at("Alice", &"1".to_string()).unwrap_err(),
+            {
+                bank.freeze_account("Bob").unwrap();
+                bank.deposit("Bob", 1.0).unwrap_err()
+            },
+            bank.deposit("Alice", Money::MAX).unwrap_err(),
+        ];
+        let predicates: [fn(&BankError) -> bool; 11] = [
+            BankError::is_account_duplication,
+            BankError::is_amount_negative,
+            BankError::is_account_not_found,
+            BankError::is_insufficient_funds,
+            BankError::is_some_account_transfer,
+            BankError::is_invalid_account_name,
+            BankError::is_history_corrupted,
+            BankError::is_non_zero_balance,
+            BankError::is_operation_not_found,
+            BankError::is_account_frozen,
+            BankError::is_balance_overflow,
+        ];
+        for (i, error) in errors.iter().enumerate() {
+            for (j, predicate) in predicates.iter().enumerate() {
+                assert_eq!(predicate(error), i == j, "{:?} predicate {}", error, j);
+            }
+        }
+    }
+
+    #[test]
+    fn test_deposit_beyond_max_balance() {
+        let mut bank = Bank::new().with_max_balance(100.0);
+        bank.create_account("Alice").unwrap();
+        bank.deposit("Alice", 60.0).unwrap();
+        bank.deposit("Alice", 40.0).unwrap();
+        let history_len = bank.get_history().unwrap().len();
+
+        assert_eq!(
+            bank.deposit("Alice", 0.01),
+            Err(BankError::BalanceOverflow(BalanceOverflowError {
+                account: "Alice".to_string(),
+                amount: Money::from(0.01),
+                max_balance: Money::from(100.0),
+            }))
+        );
+        assert_eq!(bank.get_balance("Alice"), Ok(Money::from(100.0)));
+        assert_eq!(bank.get_history().unwrap().len(), history_len);
+    }
+
+    #[test]
+    fn test_deposit_overflow() {
+        let mut bank = bank_with_accounts!("Alice");
+        bank.deposit("Alice", Money::MAX).unwrap();
+        assert!(bank
+            .deposit("Alice", Money::from_minor_units(1))
+            .unwrap_err()
+            .is_balance_overflow());
+        assert_eq!(bank.get_balance("Alice"), Ok(Money::MAX));
+    }
+
+    #[test]
+    fn test_transfer_beyond_max_balance() {
+        let mut bank = Bank::new().with_max_balance(100.0);
+        for account in ["Alice", "Bob"] {
+            bank.create_account(account).unwrap();
+        }
+        bank.deposit("Alice", 100.0).unwrap();
+        bank.deposit("Bob", 50.0).unwrap();
+        let history_len = bank.history_len();
+
+        assert!(bank
+            .transfer("Alice", "Bob", 60.0)
+            .unwrap_err()
+            .is_balance_overflow());
+        let batch = [
+            ("Alice".to_string(), "Bob".to_string(), Money::from(30.0)),
+            ("Alice".to_string(), "Bob".to_string(), Money::from(30.0)),
+        ];
+        assert!(bank
+            .transfer_batch(&batch)
+            .unwrap_err()
+            .is_balance_overflow());
+        assert_eq!(bank.get_balance("Alice"), Ok(Money::from(100.0)));
+        assert_eq!(bank.get_balance("Bob"), Ok(Money::from(50.0)));
+        assert_eq!(bank.history_len(), history_len);
+
+        bank.transfer("Alice", "Bob", 50.0).unwrap();
+        assert_eq!(bank.get_balance("Bob"), Ok(Money::from(100.0)));
+    }
+
+    #[test]
+    fn test_transfer_overflow() {
+        let mut bank = bank_with_accounts!("Alice", "Bob");
+        bank.deposit("Alice", Money::from_minor_units(1)).unwrap();
+        bank.deposit("Bob", Money::MAX).unwrap();
+        assert!(bank
+            .transfer("Alice", "Bob", Money::from_minor_units(1))
+            .unwrap_err()
+            .is_balance_overflow());
+        assert_eq!(bank.get_balance("Bob"), Ok(Money::MAX));
+    }
+
+    #[test]
+    fn test_get_balance_of_frozen_account() {
+        let mut bank = bank_with_accounts!("Alice", "Bob");
+        bank.deposit("Alice", 100.0).unwrap();
+        bank.freeze_account("Alice").unwrap();
+        assert!(bank.is_frozen("Alice"));
+
+        // reads stay permitted on a frozen account
+        assert_eq!(bank.get_balance("Alice"), Ok(Money::from(100.0)));
+        assert_eq!(bank.get_account_history("Alice").unwrap().len(), 2);
+
+        let frozen = Err(AccountFrozenError {
+            account: "Alice".to_string(),
+        }
+        .into());
+        assert_eq!(bank.deposit("Alice", 1.0), frozen);
+        assert_eq!(bank.withdraw("Alice", 1.0), frozen);
+        assert_eq!(bank.transfer("Alice", "Bob", 1.0), frozen);
+        assert_eq!(bank.transfer("Bob", "Alice", 1.0), frozen);
+        assert_eq!(bank.close_account("Alice"), frozen);
+        assert_eq!(bank.get_balance("Alice"), Ok(Money::from(100.0)));
+
+        bank.unfreeze_account("Alice").unwrap();
+        assert!(!bank.is_frozen("Alice"));
+        assert!(bank.withdraw("Alice", 1.0).is_ok());
+        assert!(bank
+            .freeze_account("Carol")
+            .unwrap_err()
+            .is_account_not_found());
+    }
+
+    #[test]
+    fn test_clone_is_independent() {
+        let mut bank = bank_with_accounts!("Alice", "Bob");
+        bank.deposit("Alice", 100.0).unwrap();
+        bank.transfer("Alice", "Bob", 40.0).unwrap();
+
+        let history_len = bank.get_history().unwrap().len();
+        let mut clone = bank.clone();
+        assert_eq!(clone.snapshot(), bank.snapshot());
+
+        clone.withdraw("Bob", 10.0).unwrap();
+        clone.create_account("Carol").unwrap();
+        clone.freeze_account("Alice").unwrap();
+
+        assert_eq!(bank.get_balance("Bob"), Ok(Money::from(40.0)));
+        assert_eq!(clone.get_balance("Bob"), Ok(Money::from(30.0)));
+        assert!(bank
+            .get_balance("Carol")
+            .unwrap_err()
+            .is_account_not_found());
+        assert!(!bank.is_frozen("Alice"));
+        assert_eq!(bank.get_history().unwrap().len(), history_len);
+        assert_eq!(clone.get_history().unwrap().len(), history_len + 2);
+    }
+
+    #[test]
+    fn test_apply_once() {
+        let mut bank = bank_with_accounts!("Alice");
+        let key = Some("deposit-1".to_string());
+        assert!(!bank.is_idempotency_key_used("deposit-1"));
+
+        let first = bank
+            .apply_once(key.clone(), |bank| bank.deposit("Alice", 100.0))
+            .unwrap();
+        let retry = bank
+            .apply_once(key, |bank| bank.deposit("Alice", 100.0))
+            .unwrap();
+        assert_eq!(first, retry);
+        assert!(bank.is_idempotency_key_used("deposit-1"));
+        assert_eq!(bank.get_balance("Alice"), Ok(Money::from(100.0)));
+
+        bank.apply_once(None, |bank| bank.deposit("Alice", 100.0))
+            .unwrap();
+        assert_eq!(bank.get_balance("Alice"), Ok(Money::from(200.0)));
+
+        let key = Some("withdraw-1".to_string());
+        assert!(bank
+            .apply_once(key.clone(), |bank| bank.withdraw("Alice", 500.0))
+            .is_err());
+        assert!(!bank.is_idempotency_key_used("withdraw-1"));
+        bank.apply_once(key, |bank| bank.withdraw("Alice", 50.0))
+            .unwrap();
+        assert_eq!(bank.get_balance("Alice"), Ok(Money::from(150.0)));
+    }
+
+    #[test]
+    fn test_latest_operations() {
+        let mut bank = bank_with_accounts!("Alice", "Bob");
+        let deposit_id = bank.deposit("Alice", 100.0).unwrap();
+        let transfer_id = bank.transfer("Alice", "Bob", 50.0).unwrap();
+
+        let latest = bank.latest_operations(2);
+        assert_eq!(latest.len(), 2);
+        assert_eq!(latest[0].id, deposit_id);
+        assert_eq!(latest[1].id, transfer_id);
+        assert_eq!(bank.latest_operations(10).len(), 4);
+        assert!(bank.latest_operations(0).is_empty());
+    }
+
     #[test]
     fn test_get_history() {
         let mut bank = bank_with_accounts!("Alice", "Bob");
@@ -925,12 +2934,143 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_display_operation() {
+        let deposit = Operation::new("1".to_string(), "Alice", 100.0, OperationType::Deposit)
+            .with_category(Some("salary".to_string()));
+        assert_eq!(deposit.to_string(), "1: deposit 100 to Alice [salary]");
+        let transfer = Operation::new(
+            "2".to_string(),
+            "Alice",
+            25.5,
+            OperationType::Transfer {
+                target_account: "Bob".to_string(),
+            },
+        );
+        assert_eq!(transfer.to_string(), "2: transfer 25.5 from Alice to Bob");
+    }
+
+    #[test]
+    fn test_create_account_with_balance() {
+        let mut bank = Bank::new();
+        bank.create_account_with_balance("Alice", 100.0).unwrap();
+        bank.create_account_with_balance("Bob", 0.0).unwrap();
+
+        assert_eq!(bank.get_balance("Alice"), Ok(Money::from(100.0)));
+        let history = bank.get_account_history("Alice").unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[1].operation_type, OperationType::Deposit);
+        assert_eq!(history[1].amount, Money::from(100.0));
+        assert_eq!(bank.get_account_history("Bob").unwrap().len(), 1);
+
+        assert!(bank
+            .create_account_with_balance("Carol", -1.0)
+            .unwrap_err()
+            .is_amount_negative());
+        assert!(bank
+            .get_balance("Carol")
+            .unwrap_err()
+            .is_account_not_found());
+    }
+
+    #[test]
+    fn test_from_balances() {
+        let bank = Bank::from_balances(&[
+            ("Alice", Money::from(100.0)),
+            ("Bob", MONEY_ZERO),
+            ("Carol", Money::from(-25.5)),
+        ]);
+        assert_eq!(bank.get_balance("Alice"), Ok(Money::from(100.0)));
+        assert_eq!(bank.get_balance("Bob"), Ok(MONEY_ZERO));
+        assert_eq!(bank.get_balance("Carol"), Ok(Money::from(-25.5)));
+        assert_eq!(bank.total_balance(), Money::from(74.5));
+        assert_eq!(bank.history_len(), 5);
+    }
+
+    #[test]
+    fn test_display_summary() {
+        let mut bank = bank_with_accounts!("Alice", "Bob");
+        bank.deposit("Alice", 100.0).unwrap();
+        bank.transfer("Alice", "Bob", 50.0).unwrap();
+
+        assert_eq!(
+            bank.to_string(),
+            "Bank: 2 accounts, total balance 100, 4 operations"
+        );
+    }
+
+    #[test]
+    fn test_operation_new() {
+        let operation = Operation::new(
+            "id".to_string(),
+            "Alice",
+            10.0,
+            OperationType::Transfer {
+                target_account: "Bob".to_string(),
+            },
+        );
+        assert_eq!(operation.id, "id");
+        assert_eq!(operation.source_account, "Alice");
+        assert_eq!(operation.amount, 10.0);
+        assert_eq!(
+            operation.operation_type,
+            OperationType::Transfer {
+                target_account: "Bob".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_sum_by_category() {
+        let mut bank = bank_with_accounts!("Alice");
+        let salary = Some("salary".to_string());
+        bank.deposit_with_category("Alice", 100.0, salary.clone())
+            .unwrap();
+        bank.deposit_with_category("Alice", 50.0, salary).unwrap();
+        bank.withdraw_with_category("Alice", 30.0, Some("groceries".to_string()))
+            .unwrap();
+        bank.deposit("Alice", 10.0).unwrap();
+
+        assert_eq!(
+            bank.sum_by_category(),
+            BTreeMap::from([
+                ("groceries".to_string(), Money::from(-30.0)),
+                ("salary".to_string(), Money::from(150.0))
+            ])
+        );
+    }
+
+    #[test]
+    fn test_operations() {
+        let mut bank = bank_with_accounts!("Alice", "Bob");
+        bank.deposit("Alice", 100.0).unwrap();
+        bank.transfer("Alice", "Bob", 50.0).unwrap();
+
+        let history = bank.get_history().unwrap();
+        assert!(bank.operations().eq(history.iter()));
+        assert_eq!(bank.history_len(), history.len());
+    }
+
+    #[test]
+    fn test_get_account_history_corrupted() {
+        let mut bank = bank_with_accounts!("Alice");
+        let transaction_id = bank.deposit("Alice", 100.0).unwrap();
+        bank.drop_history_entry(&transaction_id);
+
+        assert_eq!(
+            bank.get_account_history("Alice"),
+            Err(BankError::HistoryCorrupted(
+                HistoryCorruptedError::MissingOperation { transaction_id }
+            ))
+        );
+    }
+
     #[test]
     fn test_replay_history() {
         let mut source_bank = bank_with_accounts!("Alice", "Bob");
         source_bank.deposit("Alice", 100.0).unwrap();
         source_bank.transfer("Alice", "Bob", 50.0).unwrap();
-        let target_bank = Bank::replay_history(source_bank.get_history().unwrap().into_iter());
+        let target_bank = Bank::replay_history(source_bank.get_history().unwrap().iter());
         assert_eq!(target_bank.get_balance("Alice").unwrap(), 50.0);
         assert_eq!(target_bank.get_balance("Bob").unwrap(), 50.0);
         // Checking Alice's history
@@ -966,6 +3106,79 @@ mod tests {
             Err(res) => panic!("Unexpected error: {:?}", res),
         }
     }
+    #[test]
+    fn test_validate_history() {
+        let mut source_bank = bank_with_accounts!("Alice", "Bob");
+        source_bank.deposit("Alice", 100.0).unwrap();
+        source_bank.transfer("Alice", "Bob", 50.0).unwrap();
+        let history = source_bank.get_history().unwrap();
+        assert_eq!(Bank::validate_history(history.iter()), Ok(()));
+    }
+
+    #[test]
+    fn test_validate_corrupt_history() {
+        let mut source_bank = bank_with_accounts!("Alice");
+        source_bank.deposit("Alice", 100.0).unwrap();
+        let mut history = source_bank.get_history().unwrap();
+        history.push(Operation::new(
+            "corrupt".to_string(),
+            "Alice",
+            150.0,
+            OperationType::Withdraw,
+        ));
+
+        match Bank::validate_history(history.iter()) {
+            Ok(_) => panic!("Unexpected success for corrupt history"),
+            Err((id, err)) => {
+                assert_eq!(id, "corrupt");
+                assert_eq!(
+                    err,
+                    InsufficientFundsError {
+                        account: "Alice".to_string(),
+                        amount: Money::from(150.0),
+                        balance: Money::from(100.0),
+                    }
+                    .into()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_import_operations() {
+        let mut bank = bank_with_accounts!("Alice");
+        let operations = [
+            Operation::new(
+                "1".to_string(),
+                "Bob",
+                MONEY_ZERO,
+                OperationType::CreateAccount,
+            ),
+            Operation::new("2".to_string(), "Bob", 100.0, OperationType::Deposit),
+            Operation::new("3".to_string(), "Alice", 50.0, OperationType::Withdraw),
+        ];
+
+        assert_eq!(
+            bank.import_operations(operations.iter()),
+            (2, vec!["3".to_string()])
+        );
+        assert_eq!(bank.get_balance("Bob"), Ok(Money::from(100.0)));
+        assert_eq!(bank.get_balance("Alice"), Ok(MONEY_ZERO));
+    }
+
+    #[test]
+    fn test_accounts_in_overdraft() {
+        let mut bank = bank_with_accounts!("Alice", "Bob");
+        bank.deposit("Alice", 100.0).unwrap();
+        assert!(bank.accounts_in_overdraft().is_empty());
+
+        *bank.accounts.get("Bob").unwrap().borrow_mut() -= Money::from(20.0);
+        assert_eq!(
+            bank.accounts_in_overdraft(),
+            vec![("Bob".to_string(), Money::from(-20.0))]
+        );
+    }
+
     #[test]
     fn test_get_operation_by_id() {
         let mut bank = bank_with_accounts!("Alice", "Bob");
@@ -1 +1,2 @@
 pub mod bank;
+pub mod money;
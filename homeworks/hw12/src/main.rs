@@ -1,4 +1,4 @@
-use bank_engine::bank::{Bank, BankError, BankTrait};
+use bank_engine::bank::{Bank, BankError, BankTrait, BankView};
 
 fn main() -> Result<(), BankError> {
     // Instantiate the bank
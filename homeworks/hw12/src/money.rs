@@ -0,0 +1,211 @@
+//! The money amounts of the bank and their formatting for display.
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt::{Display, Formatter};
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Neg, Sub, SubAssign};
+
+/// The number of minor units, cents, in a major unit.
+const MINOR_UNITS: i64 = 100;
+
+/// A money amount stored as an integer number of minor units, so the arithmetic is exact.
+///
+/// On the wire the amount is a decimal number of major units, e.g. `12.34`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Money(i64);
+
+impl Money {
+    pub const ZERO: Money = Money(0);
+    pub const MAX: Money = Money(i64::MAX);
+
+    /// Creates the amount from the number of minor units.
+    pub const fn from_minor_units(minor_units: i64) -> Self {
+        Money(minor_units)
+    }
+
+    /// Returns the number of minor units.
+    pub const fn minor_units(&self) -> i64 {
+        self.0
+    }
+
+    /// Adds the amounts, `None` if the sum overflows.
+    pub fn checked_add(self, rhs: Money) -> Option<Money> {
+        self.0.checked_add(rhs.0).map(Money)
+    }
+
+    /// Converts the amount to a decimal number of major units.
+    pub fn to_f64(&self) -> f64 {
+        self.0 as f64 / MINOR_UNITS as f64
+    }
+}
+
+impl From<f64> for Money {
+    /// Rounds the decimal number of major units to the nearest minor unit.
+    fn from(amount: f64) -> Self {
+        Money((amount * MINOR_UNITS as f64).round() as i64)
+    }
+}
+
+impl From<Money> for f64 {
+    fn from(amount: Money) -> Self {
+        amount.to_f64()
+    }
+}
+
+impl PartialEq<f64> for Money {
+    fn eq(&self, other: &f64) -> bool {
+        *self == Money::from(*other)
+    }
+}
+
+impl Display for Money {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_f64())
+    }
+}
+
+impl Add for Money {
+    type Output = Money;
+
+    fn add(self, rhs: Money) -> Money {
+        Money(self.0 + rhs.0)
+    }
+}
+
+impl Sub for Money {
+    type Output = Money;
+
+    fn sub(self, rhs: Money) -> Money {
+        Money(self.0 - rhs.0)
+    }
+}
+
+impl Neg for Money {
+    type Output = Money;
+
+    fn neg(self) -> Money {
+        Money(-self.0)
+    }
+}
+
+impl AddAssign for Money {
+    fn add_assign(&mut self, rhs: Money) {
+        self.0 += rhs.0;
+    }
+}
+
+impl SubAssign for Money {
+    fn sub_assign(&mut self, rhs: Money) {
+        self.0 -= rhs.0;
+    }
+}
+
+impl Sum for Money {
+    fn sum<I: Iterator<Item = Money>>(iter: I) -> Money {
+        iter.fold(Money::ZERO, Add::add)
+    }
+}
+
+impl Serialize for Money {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_f64(self.to_f64())
+    }
+}
+
+impl<'de> Deserialize<'de> for Money {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        f64::deserialize(deserializer).map(Money::from)
+    }
+}
+
+/// The locales supported by [`format_money_locale`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Locale {
+    /// `1,000.00`
+    En,
+    /// `1 000,00`
+    Ru,
+}
+
+impl Locale {
+    fn thousands_separator(&self) -> char {
+        match self {
+            Locale::En => ',',
+            Locale::Ru => ' ',
+        }
+    }
+
+    fn decimal_separator(&self) -> char {
+        match self {
+            Locale::En => '.',
+            Locale::Ru => ',',
+        }
+    }
+}
+
+/// Formats the amount with two decimal places and the separators of the locale.
+///
+/// # Arguments
+///
+/// * `amount` - The amount to format.
+/// * `locale` - The locale defining the thousands and decimal separators.
+///
+/// # Examples
+///
+/// ```
+/// use bank_engine::money::{format_money_locale, Locale};
+///
+/// assert_eq!(format_money_locale(1000.0, Locale::En), "1,000.00");
+/// ```
+pub fn format_money_locale(amount: f64, locale: Locale) -> String {
+    let formatted = format!("{:.2}", amount.abs());
+    let (integer, fraction) = formatted.split_once('.').unwrap_or((&formatted, "00"));
+
+    let mut grouped = String::new();
+    for (i, digit) in integer.chars().enumerate() {
+        if i > 0 && (integer.len() - i) % 3 == 0 {
+            grouped.push(locale.thousands_separator());
+        }
+        grouped.push(digit);
+    }
+
+    let sign = if amount < 0.0 && formatted != "0.00" {
+        "-"
+    } else {
+        ""
+    };
+    format!(
+        "{}{}{}{}",
+        sign,
+        grouped,
+        locale.decimal_separator(),
+        fraction
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_money_locale() {
+        assert_eq!(format_money_locale(1000.0, Locale::En), "1,000.00");
+        assert_eq!(format_money_locale(1000.0, Locale::Ru), "1 000,00");
+        assert_eq!(format_money_locale(1234567.891, Locale::En), "1,234,567.89");
+        assert_eq!(format_money_locale(-999.5, Locale::Ru), "-999,50");
+        assert_eq!(format_money_locale(0.0, Locale::En), "0.00");
+    }
+
+    #[test]
+    fn test_money_is_exact() {
+        let total: Money = (0..10).map(|_| Money::from(0.1)).sum();
+        assert_eq!(total, Money::from(1.0));
+        assert_eq!(total.minor_units(), 100);
+        assert_eq!(total.to_f64(), 1.0);
+    }
+
+    #[test]
+    fn test_money_display() {
+        assert_eq!(Money::from(12.346).to_string(), "12.35");
+        assert_eq!((-Money::from(100.0)).to_string(), "-100");
+    }
+}
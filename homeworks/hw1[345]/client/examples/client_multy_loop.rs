@@ -3,11 +3,12 @@ use log::{debug, error, info};
 use rand::Rng;
 use std::error::Error;
 
-use shared::constants::{LOG_LEVEL, SERVER_ADDRESS};
+use shared::constants::SERVER_ADDRESS;
+use shared::logging::{init_logging, LogConfig};
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
-    env_logger::init_from_env(env_logger::Env::default().default_filter_or(LOG_LEVEL));
+    init_logging(&LogConfig::from_env())?;
     let mut hanlers = vec![];
     const NUM_THREADS: usize = 5;
     for i in 0..NUM_THREADS {
@@ -1,23 +1,34 @@
-use client::client::BankClient;
+use client::client::{retry_once_on_deserialize, BankClient};
 use log::info;
 use std::error::Error;
+use tokio::sync::Mutex;
 
-use shared::constants::{LOG_LEVEL, SERVER_ADDRESS};
+use shared::constants::SERVER_ADDRESS;
+use shared::logging::{init_logging, LogConfig};
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
-    // Initialize the logger based on the environment variable `LOG_LEVEL`.
-    env_logger::init_from_env(env_logger::Env::default().default_filter_or(LOG_LEVEL));
+    // Initialize the logger, the environment variable `BANK_LOG_FILE` redirects it to a file.
+    init_logging(&LogConfig::from_env())?;
     // Connect to the bank server.
-    let mut client = BankClient::connect(SERVER_ADDRESS).await?;
+    let shared_client = Mutex::new(BankClient::connect(SERVER_ADDRESS).await?);
+    let client = &shared_client;
     // Check if there was an error connecting to the server.
     info!("Successfully connected to the bank server");
-    // Create an account with the name "Hello".
-    let _ = client.create_account("Hello").await?;
-    let deposit_transaction_id = client.deposit("Hello", 100.0).await?;
+    // Create an account with the name "Hello", retrying once if the server could not read the request.
+    let _ =
+        retry_once_on_deserialize(
+            || async move { client.lock().await.create_account("Hello").await },
+        )
+        .await?;
+    let deposit_transaction_id =
+        retry_once_on_deserialize(
+            || async move { client.lock().await.deposit("Hello", 100.0).await },
+        )
+        .await?;
     info!(
         "successfully deposited with transaction id: {}",
         deposit_transaction_id
     );
-    client.shutdown().await;
+    shared_client.into_inner().shutdown().await?;
     Ok(())
 }
@@ -2,11 +2,12 @@ use client::client::BankClient;
 use log::{error, info};
 use std::error::Error;
 
-use shared::constants::{LOG_LEVEL, SERVER_ADDRESS};
+use shared::constants::SERVER_ADDRESS;
+use shared::logging::{init_logging, LogConfig};
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
-    // Initialize the logger based on the environment variable `LOG_LEVEL`.
-    env_logger::init_from_env(env_logger::Env::default().default_filter_or(LOG_LEVEL));
+    // Initialize the logger, the environment variable `BANK_LOG_FILE` redirects it to a file.
+    init_logging(&LogConfig::from_env())?;
 
     // Connect to the bank server.
     let mut client = BankClient::connect(SERVER_ADDRESS).await?;
@@ -28,6 +29,6 @@ async fn main() -> Result<(), Box<dyn Error>> {
     if let Err(err) = result {
         error!("Withdraw error: {:?}", err);
     }
-    client.shutdown().await;
+    client.shutdown().await?;
     Ok(())
 }
@@ -6,17 +6,36 @@ use shared::models::{
     DepositParams, GetBalanceAccountRequestParams, OpenAccountRequestParams, Request,
     RequestPayload, Response, ResponsePayload, TransferParams, WithdrawParams,
 };
-use shared::{Operation, TransactionId};
+use shared::{Operation, OperationType, TransactionId};
+use std::collections::HashMap;
 use std::fmt::{Display, Formatter};
+use std::future::Future;
 use std::io;
+use std::time::Duration;
 
 use thiserror::Error;
 use tokio::net::{TcpStream, ToSocketAddrs};
+use tokio_util::sync::CancellationToken;
 
 use tokio::io::AsyncWriteExt;
 
+/// The delay between the polls of the server while waiting for an operation.
+const OPERATION_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// An operation performed by the client, kept in the local log.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoggedOp {
+    pub id: TransactionId,
+    pub operation_type: OperationType,
+    pub amount: f64,
+}
+
 pub struct BankClient {
     stream: TcpStream,
+    balance_cache: Option<HashMap<String, f64>>,
+    local_log: Vec<LoggedOp>,
+    local_log_capacity: Option<usize>,
+    cancellation_token: Option<CancellationToken>,
 }
 
 impl BankClient {
@@ -51,14 +70,203 @@ impl BankClient {
         BankClient::handshake(stream).await
     }
 
+    /// Establishes a connection to the bank server, giving up if the server does not answer in time.
+    ///
+    /// The timeout applies to the connection and to the handshake separately.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ConnectError::Timeout`] if the connection or the handshake takes longer than
+    /// `timeout`.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use client::client::BankClient;
+    /// use std::time::Duration;
+    ///
+    /// # async fn run() -> shared::errors::ConnectResult<()> {
+    /// let client = BankClient::connect_timeout("127.0.0.1:8080", Duration::from_secs(1)).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn connect_timeout<Addrs>(addr: Addrs, timeout: Duration) -> ConnectResult<Self>
+    where
+        Addrs: ToSocketAddrs,
+    {
+        let stream = tokio::time::timeout(timeout, TcpStream::connect(addr))
+            .await
+            .map_err(|_| ConnectError::Timeout)??;
+        tokio::time::timeout(timeout, BankClient::handshake(stream))
+            .await
+            .map_err(|_| ConnectError::Timeout)?
+    }
+
+    /// Enables the local cache of account balances.
+    ///
+    /// With the cache enabled `get_balance` only asks the server on a cache miss, while
+    /// `deposit`, `withdraw` and `transfer` invalidate the balances of the affected accounts.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use client::client::BankClient;
+    ///
+    /// # async fn run() -> shared::errors::ConnectResult<()> {
+    /// let client = BankClient::connect("127.0.0.1:8080").await?.with_balance_cache();
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_balance_cache(mut self) -> Self {
+        self.balance_cache = Some(HashMap::new());
+        self
+    }
+
+    /// Enables the local log of the operations performed by the client.
+    ///
+    /// # Arguments
+    ///
+    /// * `capacity` - The number of the most recent operations to keep.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use client::client::BankClient;
+    ///
+    /// # async fn run() -> shared::errors::ConnectResult<()> {
+    /// let client = BankClient::connect("127.0.0.1:8080").await?.with_local_log(100);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_local_log(mut self, capacity: usize) -> Self {
+        self.local_log_capacity = Some(capacity);
+        self
+    }
+
+    /// Aborts the operations in progress as soon as the token is cancelled, e.g. on shutdown.
+    ///
+    /// A cancelled operation returns `Cancelled`, its response may still arrive later,
+    /// so the connection should be shut down afterwards.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use client::client::BankClient;
+    /// use tokio_util::sync::CancellationToken;
+    ///
+    /// # async fn run() -> shared::errors::ConnectResult<()> {
+    /// let token = CancellationToken::new();
+    /// let client = BankClient::connect("127.0.0.1:8080")
+    ///     .await?
+    ///     .with_cancellation_token(token.clone());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_cancellation_token(mut self, token: CancellationToken) -> Self {
+        self.cancellation_token = Some(token);
+        self
+    }
+
+    /// Sends the request and waits for the response, unless the cancellation token is cancelled.
+    async fn round_trip(&mut self, request: &Request) -> ResponseResult<Response> {
+        let stream = &mut self.stream;
+        let exchange = async {
+            request.send(stream).await?;
+            Ok::<_, ResponseError>(Response::new(stream).await?)
+        };
+        let response = match self.cancellation_token.as_ref() {
+            None => exchange.await?,
+            Some(token) => tokio::select! {
+                result = exchange => result?,
+                _ = token.cancelled() => {
+                    error!("Operation cancelled: {:?}", request.payload);
+                    return Err(ResponseError::Cancelled);
+                }
+            },
+        };
+        if let ResponsePayload::DeserializeError { message, .. } = &response.payload {
+            error!("The server could not deserialize the request: {}", message);
+            return Err(ResponseError::RequestNotDeserialized(message.to_owned()));
+        }
+        Ok(response)
+    }
+
+    /// Returns the successful operations performed by the client, oldest first.
+    ///
+    /// The log is empty unless it is enabled with `with_local_log`.
+    pub fn local_log(&self) -> &[LoggedOp] {
+        &self.local_log
+    }
+
+    /// Appends the operation to the local log, dropping the oldest one when the log is full.
+    fn log_operation(&mut self, id: &TransactionId, operation_type: OperationType, amount: f64) {
+        let Some(capacity) = self.local_log_capacity else {
+            return;
+        };
+        if capacity == 0 {
+            return;
+        }
+        if self.local_log.len() == capacity {
+            self.local_log.remove(0);
+        }
+        self.local_log.push(LoggedOp {
+            id: id.to_owned(),
+            operation_type,
+            amount,
+        });
+    }
+
+    /// Drops the cached balance of the account, so the next `get_balance` asks the server.
+    fn invalidate_balance(&mut self, account: &str) {
+        if let Some(cache) = self.balance_cache.as_mut() {
+            cache.remove(account);
+        }
+    }
+
     /// Sends a request to the server to close the connection and shuts down the stream.
-    pub async fn shutdown(&mut self) {
+    ///
+    /// The client is consumed, so a closed connection can not be used or shut down again:
+    ///
+    /// ```compile_fail
+    /// # async fn run(client: client::client::BankClient) {
+    /// client.shutdown().await.unwrap();
+    /// client.shutdown().await.unwrap();
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ResponseError::Io`] if the request could not be sent or the stream shut down.
+    pub async fn shutdown(mut self) -> ResponseResult<()> {
         let data_req = Request {
             payload: RequestPayload::CloseConnection,
         };
-        let json = serde_json::to_string(&data_req).unwrap();
-        let _ = self.stream.write(json.as_bytes()).await;
-        let _ = self.stream.shutdown().await;
+        data_req.send(&mut self.stream).await?;
+        self.stream.flush().await?;
+        self.stream.shutdown().await?;
+        Ok(())
+    }
+
+    /// Checks that the server still answers on the connection.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the ping is answered with a handshake, `false` otherwise, IO errors included.
+    pub async fn is_connected(&mut self) -> bool {
+        let data_req = Request {
+            payload: RequestPayload::Ping,
+        };
+        if let Err(err) = data_req.send(&mut self.stream).await {
+            debug!("ping failed: {}", err);
+            return false;
+        }
+        match Response::new(&mut self.stream).await {
+            Ok(response) => response.payload == ResponsePayload::HandShakeEstablished,
+            Err(err) => {
+                debug!("ping failed: {}", err);
+                false
+            }
+        }
     }
 
     /// Performs a handshake with the bank server to establish a secure connection.
@@ -76,9 +284,7 @@ impl BankClient {
         let data_req = Request {
             payload: RequestPayload::Ping,
         };
-        let json = serde_json::to_string(&data_req).unwrap();
-
-        let _ = stream.write(json.as_bytes()).await?;
+        data_req.send(&mut stream).await?;
 
         let resp = Response::new(&mut stream).await?;
         if resp.payload != ResponsePayload::HandShakeEstablished {
@@ -87,7 +293,13 @@ impl BankClient {
             return Err(ConnectError::BadHandshake(msg));
         }
 
-        Ok(Self { stream })
+        Ok(Self {
+            stream,
+            balance_cache: None,
+            local_log: vec![],
+            local_log_capacity: None,
+            cancellation_token: None,
+        })
     }
     /// Creates a new bank account for the client with the specified name.
     ///
@@ -114,12 +326,11 @@ impl BankClient {
             }),
         };
         debug!("sending: {:?}", &data_req);
-        data_req.send(&mut self.stream).await?;
-
-        let response = Response::new(&mut self.stream).await?;
+        let response = self.round_trip(&data_req).await?;
         debug!("received: {:?}", &response);
 
         if let ResponsePayload::AccountCreated(transaction_id) = &response.payload {
+            self.log_operation(transaction_id, OperationType::CreateAccount, 0.0);
             Ok((*transaction_id).to_owned())
         } else {
             Err(ResponseError::unexpected_response(&response.payload))
@@ -141,18 +352,20 @@ impl BankClient {
     ///
     /// ```
     pub async fn deposit(&mut self, account: &str, amount: f64) -> ResponseResult<TransactionId> {
+        self.invalidate_balance(account);
         let data_req = Request {
             payload: RequestPayload::Deposit(DepositParams {
                 account: account.to_string(),
                 amount,
+                category: None,
+                idempotency_key: None,
             }),
         };
         debug!("sending: {:?}", &data_req);
-        data_req.send(&mut self.stream).await?;
-
-        let response = Response::new(&mut self.stream).await?;
+        let response = self.round_trip(&data_req).await?;
 
         if let ResponsePayload::DepositSuccess(transaction_id) = response.payload {
+            self.log_operation(&transaction_id, OperationType::Deposit, amount);
             Ok(transaction_id.to_owned())
         } else {
             Err(ResponseError::unexpected_response(&response.payload))
@@ -171,19 +384,21 @@ impl BankClient {
     ///
     /// ```
     pub async fn withdraw(&mut self, account: &str, amount: f64) -> ResponseResult<TransactionId> {
+        self.invalidate_balance(account);
         let data_req = Request {
             payload: RequestPayload::Withdraw(WithdrawParams {
                 account: account.to_string(),
                 amount,
+                category: None,
+                idempotency_key: None,
             }),
         };
         debug!("sending: {:?}", &data_req);
-        data_req.send(&mut self.stream).await?;
-
-        let response = Response::new(&mut self.stream).await?;
+        let response = self.round_trip(&data_req).await?;
         debug!("received: {:?}", &response);
 
         if let ResponsePayload::WithdrawSuccess(transaction_id) = response.payload {
+            self.log_operation(&transaction_id, OperationType::Withdraw, amount);
             Ok(transaction_id.to_owned())
         } else if let ResponsePayload::WithdrawalError(error_message) = response.payload {
             Err(ResponseError::WithdrawalError(error_message))
@@ -211,6 +426,8 @@ impl BankClient {
         receiver_account: &str,
         amount: f64,
     ) -> ResponseResult<TransactionId> {
+        self.invalidate_balance(sender_account);
+        self.invalidate_balance(receiver_account);
         let data_req = Request {
             payload: RequestPayload::Transfer(TransferParams {
                 sender_account: sender_account.to_string(),
@@ -220,12 +437,14 @@ impl BankClient {
         };
 
         debug!("sending: {:?}", &data_req);
-        data_req.send(&mut self.stream).await?;
-        debug!("sending after");
-        let response = Response::new(&mut self.stream).await?;
+        let response = self.round_trip(&data_req).await?;
         debug!("received: {:?}", &response);
 
         if let ResponsePayload::TransferSuccess(transaction_id) = response.payload {
+            let operation_type = OperationType::Transfer {
+                target_account: receiver_account.to_string(),
+            };
+            self.log_operation(&transaction_id, operation_type, amount);
             Ok(transaction_id.to_owned())
         } else if let ResponsePayload::SomeAccountError(error_message) = response.payload {
             error!("Transfer error {:?}", error_message);
@@ -243,6 +462,65 @@ impl BankClient {
         }
     }
 
+    /// Transfers the amount and fetches the updated histories of both accounts in a single batch.
+    ///
+    /// # Arguments
+    ///
+    /// * `sender_account` - The account from which the amount will be transferred.
+    /// * `receiver_account` - The account to which the amount will be transferred.
+    /// * `amount` - The amount to be transferred.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the transfer fails or the batch response does not hold both histories.
+    ///
+    /// # Returns
+    ///
+    /// The transaction id with the histories of the sender and the receiver.
+    ///
+    /// ```
+    pub async fn transfer_with_receipts(
+        &mut self,
+        sender_account: &str,
+        receiver_account: &str,
+        amount: f64,
+    ) -> ResponseResult<(TransactionId, Vec<Operation>, Vec<Operation>)> {
+        self.invalidate_balance(sender_account);
+        self.invalidate_balance(receiver_account);
+        let data_req = Request {
+            payload: RequestPayload::Batch(vec![
+                RequestPayload::Transfer(TransferParams {
+                    sender_account: sender_account.to_string(),
+                    receiver_account: receiver_account.to_string(),
+                    amount,
+                }),
+                RequestPayload::GetHistoryForAccount(sender_account.to_string()),
+                RequestPayload::GetHistoryForAccount(receiver_account.to_string()),
+            ]),
+        };
+        debug!("sending: {:?}", &data_req);
+        let response = self.round_trip(&data_req).await?;
+        debug!("received: {:?}", &response);
+
+        if let ResponsePayload::Batch(payloads) = &response.payload {
+            if let [ResponsePayload::TransferSuccess(transaction_id), ResponsePayload::History(sender_history), ResponsePayload::History(receiver_history)] =
+                payloads.as_slice()
+            {
+                let receipts = (
+                    transaction_id.to_owned(),
+                    sender_history.clone(),
+                    receiver_history.clone(),
+                );
+                let operation_type = OperationType::Transfer {
+                    target_account: receiver_account.to_string(),
+                };
+                self.log_operation(transaction_id, operation_type, amount);
+                return Ok(receipts);
+            }
+        }
+        Err(ResponseError::unexpected_response(&response.payload))
+    }
+
     /// Retrieves the balance of the specified account.
     ///
     /// # Arguments
@@ -255,9 +533,18 @@ impl BankClient {
     ///
     /// # Returns
     ///
-    /// The balance of the specified account.
+    /// The balance of the specified account, served from the local cache when it is enabled.
     /// ```
     pub async fn get_balance(&mut self, account: &str) -> ResponseResult<f64> {
+        if let Some(balance) = self
+            .balance_cache
+            .as_ref()
+            .and_then(|cache| cache.get(account))
+        {
+            debug!("cached balance for {}: {}", account, balance);
+            return Ok(*balance);
+        }
+
         let data_req = Request {
             payload: RequestPayload::GetBalance(GetBalanceAccountRequestParams {
                 account: account.to_string(),
@@ -265,14 +552,15 @@ impl BankClient {
         };
 
         debug!("sending: {:?}", &data_req);
-        data_req.send(&mut self.stream).await?;
-
-        let response = Response::new(&mut self.stream).await?;
+        let response = self.round_trip(&data_req).await?;
         debug!("received: {:?}", &response);
 
         let bal = &response.payload;
 
         if let ResponsePayload::Balance(aviable_balance) = bal {
+            if let Some(cache) = self.balance_cache.as_mut() {
+                cache.insert(account.to_string(), *aviable_balance);
+            }
             return Ok(*aviable_balance);
         }
 
@@ -296,9 +584,7 @@ impl BankClient {
         let data_req = Request {
             payload: RequestPayload::GetHistory(),
         };
-        data_req.send(&mut self.stream).await?;
-
-        let response = Response::new(&mut self.stream).await?;
+        let response = self.round_trip(&data_req).await?;
         debug!("received: {:?}", &response);
 
         let bal = &response.payload;
@@ -312,6 +598,144 @@ impl BankClient {
         }
         .into())
     }
+    /// Renders the transaction history as a numbered report, one operation per line.
+    ///
+    /// # Arguments
+    ///
+    /// * `account` - The account to render the history of, the whole history if `None`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the history can not be retrieved.
+    ///
+    /// ```
+    pub async fn print_history(&mut self, account: Option<&str>) -> ResponseResult<String> {
+        let history = match account {
+            Some(account) => self.get_history_for_account(account).await?,
+            None => self.get_history().await?,
+        };
+        Ok(history
+            .iter()
+            .enumerate()
+            .map(|(ind, operation)| format!("{}. {}\n", ind + 1, operation))
+            .collect())
+    }
+
+    /// Retrieves the operation with the specified transaction identifier.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The transaction identifier of the operation.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there is an error response or if the response payload is not `Operation`.
+    ///
+    /// # Returns
+    ///
+    /// The operation, or `None` if it is not in the history yet.
+    ///
+    /// ```
+    pub async fn get_operation(&mut self, id: &str) -> ResponseResult<Option<Operation>> {
+        let data_req = Request {
+            payload: RequestPayload::GetOperation(id.to_string()),
+        };
+        debug!("sending: {:?}", &data_req);
+        let response = self.round_trip(&data_req).await?;
+        debug!("received: {:?}", &response);
+
+        if let ResponsePayload::Operation(operation) = response.payload {
+            Ok(operation)
+        } else {
+            Err(ResponseError::unexpected_response(&response.payload))
+        }
+    }
+
+    /// Polls the server until the operation with the specified transaction identifier appears.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The transaction identifier of the operation.
+    /// * `timeout` - How long to wait for the operation.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Timeout` if the operation does not appear in time, or an error of `get_operation`.
+    ///
+    /// # Returns
+    ///
+    /// The operation as soon as it is in the history.
+    ///
+    /// ```
+    pub async fn wait_for_operation(
+        &mut self,
+        id: &str,
+        timeout: Duration,
+    ) -> ResponseResult<Operation> {
+        let deadline = tokio::time::Instant::now() + timeout;
+        loop {
+            if let Some(operation) = self.get_operation(id).await? {
+                return Ok(operation);
+            }
+            if tokio::time::Instant::now() >= deadline {
+                error!("Timed out waiting for operation {}", id);
+                return Err(ResponseError::Timeout(id.to_string()));
+            }
+            tokio::time::sleep(OPERATION_POLL_INTERVAL).await;
+        }
+    }
+
+    /// Retrieves the number of operations in the transaction history without fetching them.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there is an error response or if the response payload is not `HistoryLen`.
+    ///
+    /// # Returns
+    ///
+    /// The number of operations, so the caller can decide whether to page the history.
+    ///
+    /// ```
+    pub async fn history_len(&mut self) -> ResponseResult<usize> {
+        let data_req = Request {
+            payload: RequestPayload::GetHistoryLen,
+        };
+        debug!("sending: {:?}", &data_req);
+        let response = self.round_trip(&data_req).await?;
+        debug!("received: {:?}", &response);
+
+        if let ResponsePayload::HistoryLen(len) = response.payload {
+            Ok(len)
+        } else {
+            Err(ResponseError::unexpected_response(&response.payload))
+        }
+    }
+
+    /// Retrieves the codes of the open accounts.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there is an error response or if the response payload is not `AccountList`.
+    ///
+    /// # Returns
+    ///
+    /// The account codes sorted alphabetically.
+    ///
+    /// ```
+    pub async fn list_accounts(&mut self) -> ResponseResult<Vec<String>> {
+        let data_req = Request {
+            payload: RequestPayload::ListAccounts,
+        };
+        debug!("sending: {:?}", &data_req);
+        let response = self.round_trip(&data_req).await?;
+        debug!("received: {:?}", &response);
+
+        if let ResponsePayload::AccountList(accounts) = response.payload {
+            Ok(accounts)
+        } else {
+            Err(ResponseError::unexpected_response(&response.payload))
+        }
+    }
     /// Retrieves the transaction history for the specified account.
     ///
     /// # Arguments
@@ -335,9 +759,7 @@ impl BankClient {
             payload: RequestPayload::GetHistoryForAccount(account.to_string()),
         };
         debug!("sending: {:?}", &data_req);
-        data_req.send(&mut self.stream).await?;
-
-        let response = Response::new(&mut self.stream).await?;
+        let response = self.round_trip(&data_req).await?;
         debug!("received: {:?}", &response);
 
         if let ResponsePayload::History(account_history) = &response.payload {
@@ -352,6 +774,51 @@ impl BankClient {
 
 pub type ResponseResult<T> = Result<T, ResponseError>;
 
+/// Runs the operation, retrying it once if the server could not deserialize its request.
+///
+/// Only [`ResponseError::RequestNotDeserialized`] is retried, the server has not applied the
+/// request then. A response the client could not deserialize is not retried: the server has
+/// already applied the request, so retrying a non-idempotent call, e.g. a deposit without
+/// an idempotency key, would apply it twice.
+///
+/// # Arguments
+///
+/// * `operation` - Creates the future of the operation, called once per attempt.
+///
+/// # Errors
+///
+/// Returns the error of the retry, or the first error if the server has deserialized the request.
+///
+/// # Examples
+///
+/// ```no_run
+/// use client::client::{retry_once_on_deserialize, BankClient};
+/// use tokio::sync::Mutex;
+///
+/// # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+/// let client = Mutex::new(BankClient::connect("127.0.0.1:8080").await?);
+/// let client = &client;
+/// let balance = retry_once_on_deserialize(|| async move {
+///     client.lock().await.get_balance("Alice").await
+/// })
+/// .await?;
+/// # Ok(())
+/// # }
+/// ```
+pub async fn retry_once_on_deserialize<T, F, Fut>(mut operation: F) -> ResponseResult<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = ResponseResult<T>>,
+{
+    match operation().await {
+        Err(err @ ResponseError::RequestNotDeserialized(_)) => {
+            error!("Retrying after a deserialize error: {}", err);
+            operation().await
+        }
+        result => result,
+    }
+}
+
 /// Represents an error that can occur when handling API responses.
 #[derive(Debug, Error)]
 pub enum ResponseError {
@@ -376,11 +843,32 @@ pub enum ResponseError {
 
     #[error("Withdrawal error: {0}")]
     WithdrawalError(String),
+
+    /// The server could not deserialize the request, so it was not applied.
+    #[error("Request not deserialized: {0}")]
+    RequestNotDeserialized(String),
+
+    /// The awaited operation did not appear in time.
+    #[error("Timed out waiting for operation {0}")]
+    Timeout(TransactionId),
+
+    /// The operation was aborted by the cancellation token.
+    #[error("Operation cancelled")]
+    Cancelled,
 }
 
 impl ResponseError {
+    /// Checks whether the response could not be deserialized.
+    pub fn is_deserialize_error(&self) -> bool {
+        match self {
+            ResponseError::DeserializationError(_) => true,
+            ResponseError::Io(err) => err.kind() == io::ErrorKind::InvalidData,
+            _ => false,
+        }
+    }
+
     fn unexpected_response(payload: &ResponsePayload) -> Self {
-        error!("Unexpected response payload: {:?} ", payload);
+        error!("Unexpected response payload: {} ", payload);
         UnexpectedResponse(UnexpectedResponseData {
             error_message: format!("Unexpected response: {:?}", payload),
         })
@@ -428,3 +916,369 @@ impl Display for UnexpectedResponseData {
         write!(f, "{}", self.error_message)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shared::codec;
+    use std::net::SocketAddr;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use tokio::net::{TcpListener, TcpSocket};
+
+    fn deposit_operation(id: &str) -> Operation {
+        Operation::new(id.to_string(), "Alice", 10.0, OperationType::Deposit)
+    }
+
+    fn transfer_operation(id: &str) -> Operation {
+        Operation::new(
+            id.to_string(),
+            "Alice",
+            10.0,
+            OperationType::Transfer {
+                target_account: "Bob".to_string(),
+            },
+        )
+    }
+
+    /// Starts a fake bank server for a single connection and counts the balance requests.
+    async fn spawn_fake_server(balance_requests: Arc<AtomicUsize>) -> SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            while let Ok(req) = codec::read_message::<Request, _>(&mut stream).await {
+                let payload = match req.payload {
+                    RequestPayload::Ping => ResponsePayload::HandShakeEstablished,
+                    RequestPayload::CloseConnection => break,
+                    RequestPayload::GetBalance(_) => {
+                        balance_requests.fetch_add(1, Ordering::SeqCst);
+                        ResponsePayload::Balance(100.0)
+                    }
+                    RequestPayload::Deposit(_) => ResponsePayload::DepositSuccess("1".to_string()),
+                    RequestPayload::Withdraw(_) => {
+                        ResponsePayload::WithdrawSuccess("3".to_string())
+                    }
+                    RequestPayload::Batch(requests) => ResponsePayload::Batch(
+                        requests
+                            .into_iter()
+                            .map(|request| match request {
+                                RequestPayload::Transfer(_) => {
+                                    ResponsePayload::TransferSuccess("2".to_string())
+                                }
+                                RequestPayload::GetHistoryForAccount(_) => {
+                                    ResponsePayload::History(vec![transfer_operation("2")])
+                                }
+                                _ => ResponsePayload::Error("unsupported".to_string()),
+                            })
+                            .collect(),
+                    ),
+                    RequestPayload::GetHistory() => ResponsePayload::History(vec![
+                        deposit_operation("1"),
+                        transfer_operation("2"),
+                    ]),
+                    RequestPayload::GetHistoryForAccount(_) => ResponsePayload::History(
+                        (0..100)
+                            .map(|id| deposit_operation(&id.to_string()))
+                            .collect(),
+                    ),
+                    RequestPayload::GetHistoryLen => std::future::pending().await,
+                    RequestPayload::GetOperation(id) => {
+                        ResponsePayload::Operation((id == "1").then(|| deposit_operation(&id)))
+                    }
+                    _ => ResponsePayload::Error("unsupported".to_string()),
+                };
+                Response { payload }.send(&mut stream).await.unwrap();
+            }
+        });
+        addr
+    }
+
+    #[tokio::test]
+    async fn test_balance_cache_hits_network_once() {
+        let balance_requests = Arc::new(AtomicUsize::new(0));
+        let addr = spawn_fake_server(balance_requests.clone()).await;
+        let mut client = BankClient::connect(addr)
+            .await
+            .unwrap()
+            .with_balance_cache();
+
+        assert_eq!(client.get_balance("Alice").await.unwrap(), 100.0);
+        assert_eq!(client.get_balance("Alice").await.unwrap(), 100.0);
+        assert_eq!(balance_requests.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_balance_cache_invalidated_by_deposit() {
+        let balance_requests = Arc::new(AtomicUsize::new(0));
+        let addr = spawn_fake_server(balance_requests.clone()).await;
+        let mut client = BankClient::connect(addr)
+            .await
+            .unwrap()
+            .with_balance_cache();
+
+        client.get_balance("Alice").await.unwrap();
+        client.deposit("Alice", 10.0).await.unwrap();
+        client.get_balance("Alice").await.unwrap();
+        assert_eq!(balance_requests.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_balance_without_cache() {
+        let balance_requests = Arc::new(AtomicUsize::new(0));
+        let addr = spawn_fake_server(balance_requests.clone()).await;
+        let mut client = BankClient::connect(addr).await.unwrap();
+
+        client.get_balance("Alice").await.unwrap();
+        client.get_balance("Alice").await.unwrap();
+        assert_eq!(balance_requests.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_is_connected() {
+        let addr = spawn_fake_server(Arc::new(AtomicUsize::new(0))).await;
+        let mut client = BankClient::connect(addr).await.unwrap();
+
+        assert!(client.is_connected().await);
+        Request {
+            payload: RequestPayload::CloseConnection,
+        }
+        .send(&mut client.stream)
+        .await
+        .unwrap();
+        assert!(!client.is_connected().await);
+    }
+
+    #[tokio::test]
+    async fn test_connect_timeout() {
+        // The server accepts the connection but never answers the handshake.
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (_stream, _) = listener.accept().await.unwrap();
+            std::future::pending::<()>().await;
+        });
+
+        let started = std::time::Instant::now();
+        let result = BankClient::connect_timeout(addr, Duration::from_millis(100)).await;
+        assert!(matches!(result, Err(ConnectError::Timeout)));
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[tokio::test]
+    async fn test_connect_timeout_not_established() {
+        // The listener never accepts, once its backlog is full the new connections
+        // are dropped, so the connection is never established.
+        let socket = TcpSocket::new_v4().unwrap();
+        socket.bind("127.0.0.1:0".parse().unwrap()).unwrap();
+        let addr = socket.local_addr().unwrap();
+        let _listener = socket.listen(1).unwrap();
+        let mut backlog = vec![];
+        while let Ok(stream) =
+            tokio::time::timeout(Duration::from_millis(100), TcpStream::connect(addr)).await
+        {
+            backlog.push(stream.unwrap());
+        }
+
+        let started = std::time::Instant::now();
+        let result = BankClient::connect_timeout(addr, Duration::from_millis(100)).await;
+        assert!(matches!(result, Err(ConnectError::Timeout)));
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[tokio::test]
+    async fn test_drop_after_server_closed() {
+        let addr = spawn_fake_server(Arc::new(AtomicUsize::new(0))).await;
+        let mut client = BankClient::connect(addr).await.unwrap();
+        Request {
+            payload: RequestPayload::CloseConnection,
+        }
+        .send(&mut client.stream)
+        .await
+        .unwrap();
+
+        assert!(client.get_balance("Alice").await.is_err());
+        drop(client);
+    }
+
+    #[tokio::test]
+    async fn test_shutdown() {
+        let addr = spawn_fake_server(Arc::new(AtomicUsize::new(0))).await;
+        let mut client = BankClient::connect(addr).await.unwrap();
+
+        assert!(client.is_connected().await);
+        client.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_operation() {
+        let addr = spawn_fake_server(Arc::new(AtomicUsize::new(0))).await;
+        let mut client = BankClient::connect(addr).await.unwrap();
+
+        let id = client.deposit("Alice", 10.0).await.unwrap();
+        let operation = client
+            .wait_for_operation(&id, Duration::from_secs(1))
+            .await
+            .unwrap();
+        assert_eq!(operation, deposit_operation(&id));
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_operation_timeout() {
+        let addr = spawn_fake_server(Arc::new(AtomicUsize::new(0))).await;
+        let mut client = BankClient::connect(addr).await.unwrap();
+
+        let result = client
+            .wait_for_operation("unknown", Duration::from_millis(100))
+            .await;
+        assert!(matches!(result, Err(ResponseError::Timeout(_))));
+    }
+
+    #[tokio::test]
+    async fn test_transfer_with_receipts() {
+        let addr = spawn_fake_server(Arc::new(AtomicUsize::new(0))).await;
+        let mut client = BankClient::connect(addr).await.unwrap();
+
+        let (id, sender_history, receiver_history) = client
+            .transfer_with_receipts("Alice", "Bob", 10.0)
+            .await
+            .unwrap();
+        assert!(sender_history.contains(&transfer_operation(&id)));
+        assert!(receiver_history.contains(&transfer_operation(&id)));
+    }
+
+    #[tokio::test]
+    async fn test_local_log() {
+        let addr = spawn_fake_server(Arc::new(AtomicUsize::new(0))).await;
+        let mut client = BankClient::connect(addr).await.unwrap().with_local_log(2);
+
+        client.deposit("Alice", 10.0).await.unwrap();
+        client.withdraw("Alice", 5.0).await.unwrap();
+        client
+            .transfer_with_receipts("Alice", "Bob", 3.0)
+            .await
+            .unwrap();
+        assert_eq!(
+            client.local_log(),
+            &[
+                LoggedOp {
+                    id: "3".to_string(),
+                    operation_type: OperationType::Withdraw,
+                    amount: 5.0,
+                },
+                LoggedOp {
+                    id: "2".to_string(),
+                    operation_type: OperationType::Transfer {
+                        target_account: "Bob".to_string(),
+                    },
+                    amount: 3.0,
+                },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_long_history_for_account() {
+        let addr = spawn_fake_server(Arc::new(AtomicUsize::new(0))).await;
+        let mut client = BankClient::connect(addr).await.unwrap();
+
+        let history = client.get_history_for_account("Alice").await.unwrap();
+        assert_eq!(history.len(), 100);
+        assert_eq!(history[99], deposit_operation("99"));
+        // the response spans several chunks, a fixed-size buffer would truncate it
+        let response = Response {
+            payload: ResponsePayload::History(history),
+        };
+        assert!(serde_json::to_vec(&response).unwrap().len() > 2048);
+    }
+
+    #[tokio::test]
+    async fn test_print_history() {
+        let addr = spawn_fake_server(Arc::new(AtomicUsize::new(0))).await;
+        let mut client = BankClient::connect(addr).await.unwrap();
+
+        let report = client.print_history(None).await.unwrap();
+        assert_eq!(
+            report,
+            "1. 1: deposit 10 to Alice\n2. 2: transfer 10 from Alice to Bob\n"
+        );
+
+        let report = client.print_history(Some("Alice")).await.unwrap();
+        assert!(report.starts_with("1. 0: deposit 10 to Alice\n"));
+        assert!(report.contains("100. 99: deposit 10 to Alice\n"));
+    }
+
+    #[tokio::test]
+    async fn test_cancel_mid_request() {
+        let addr = spawn_fake_server(Arc::new(AtomicUsize::new(0))).await;
+        let token = CancellationToken::new();
+        let mut client = BankClient::connect(addr)
+            .await
+            .unwrap()
+            .with_cancellation_token(token.clone());
+
+        // the fake server never answers the history length
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            token.cancel();
+        });
+        let result = tokio::time::timeout(Duration::from_secs(5), client.history_len())
+            .await
+            .expect("the cancelled request must not hang");
+        assert!(matches!(result, Err(ResponseError::Cancelled)));
+    }
+
+    #[tokio::test]
+    async fn test_retry_once_on_deserialize() {
+        let attempts = AtomicUsize::new(0);
+        let result = retry_once_on_deserialize(|| {
+            let attempt = attempts.fetch_add(1, Ordering::SeqCst);
+            async move {
+                if attempt == 0 {
+                    Err(ResponseError::RequestNotDeserialized(
+                        "EOF while parsing a value".to_string(),
+                    ))
+                } else {
+                    Ok(attempt)
+                }
+            }
+        })
+        .await;
+        assert_eq!(result.unwrap(), 1);
+        assert_eq!(attempts.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_retry_once_gives_up() {
+        let attempts = AtomicUsize::new(0);
+        let result: ResponseResult<()> = retry_once_on_deserialize(|| {
+            attempts.fetch_add(1, Ordering::SeqCst);
+            async {
+                Err(ResponseError::RequestNotDeserialized(
+                    "EOF while parsing a value".to_string(),
+                ))
+            }
+        })
+        .await;
+        assert!(matches!(
+            result,
+            Err(ResponseError::RequestNotDeserialized(_))
+        ));
+        assert_eq!(attempts.load(Ordering::SeqCst), 2);
+
+        for err in [
+            || ResponseError::Cancelled,
+            || ResponseError::from(serde_json::from_str::<Response>("{").unwrap_err()),
+            || ResponseError::from(io::Error::from(io::ErrorKind::InvalidData)),
+        ] {
+            let attempts = AtomicUsize::new(0);
+            let result: ResponseResult<()> = retry_once_on_deserialize(|| {
+                attempts.fetch_add(1, Ordering::SeqCst);
+                async move { Err(err()) }
+            })
+            .await;
+            assert!(result.is_err());
+            assert_eq!(attempts.load(Ordering::SeqCst), 1);
+        }
+    }
+}
@@ -1,21 +1,64 @@
 use log::{debug, error, info};
+use serde::de::IgnoredAny;
+use std::collections::VecDeque;
+use std::io;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::mpsc;
 use std::sync::mpsc::{channel, Receiver, Sender};
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::io::AsyncWriteExt;
 use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::broadcast;
 
 use bank_engine::bank::BankResponse::Transaction;
-use bank_engine::bank::{Bank, BankError, BankResponse, BankTrait};
-use shared::constants::{LOG_LEVEL, MAX_CHUNK_BYTE_SIZE, SERVER_ADDRESS};
+use bank_engine::bank::{Bank, BankError, BankResponse, BankTrait, BankView, Operation};
+use shared::codec;
+use shared::constants::{MAX_MESSAGE_BYTE_SIZE, SCHEMA_VERSION, SERVER_ADDRESS};
+use shared::logging::{init_logging, LogConfig};
 
 use shared::errors::ProcessingErrorsResult;
 use shared::errors::ProcessingErrorsResult::TypeMismatchError;
 use shared::models::{
-    DepositParams, GetBalanceAccountRequestParams, OpenAccountRequestParams, Request,
-    RequestPayload, Response, ResponsePayload, ResponseResult, TransferParams, WithdrawParams,
+    DepositParams, GetBalanceAccountRequestParams, ImportOperationsParams,
+    OpenAccountRequestParams, Request, RequestPayload, Response, ResponsePayload, ResponseResult,
+    TransferParams, WithdrawParams,
 };
 use RequestPayload::*;
 
+/// The environment variable holding the admin token, the admin requests are rejected without it.
+const ADMIN_TOKEN_ENV: &str = "BANK_ADMIN_TOKEN";
+
+/// The environment variable holding the number of idle seconds after which the server exits,
+/// the server runs forever without it.
+const IDLE_TIMEOUT_ENV: &str = "BANK_IDLE_TIMEOUT_SECS";
+
+/// The number of operations kept for a subscriber that reads them slower than they happen.
+const EVENT_BUFFER_SIZE: usize = 1024;
+
+/// The number of the last operations on a connection that can be undone.
+const UNDO_STACK_DEPTH: usize = 10;
+
+/// Keeps the reverse operations for the last successful operations on a connection.
+#[derive(Default)]
+struct UndoStack {
+    operations: VecDeque<RequestPayload>,
+}
+
+impl UndoStack {
+    /// Remembers the reverse operation, dropping the oldest one when the stack is full.
+    fn push(&mut self, reverse: RequestPayload) {
+        if self.operations.len() == UNDO_STACK_DEPTH {
+            self.operations.pop_front();
+        }
+        self.operations.push_back(reverse);
+    }
+
+    fn pop(&mut self) -> Option<RequestPayload> {
+        self.operations.pop_back()
+    }
+}
+
 /// The main function of the program.
 ///
 /// It initializes the logging, creates a new `Bank` object, binds a TCP listener to the specified server path,
@@ -23,7 +66,7 @@ use RequestPayload::*;
 /// and starts accepting incoming connections. For each incoming connection spawn new thread for processing requests.
 #[tokio::main(worker_threads = 1)]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    env_logger::init_from_env(env_logger::Env::default().default_filter_or(LOG_LEVEL));
+    init_logging(&LogConfig::from_env())?;
 
     let listener = TcpListener::bind(SERVER_ADDRESS).await.unwrap();
     info!(
@@ -31,14 +74,54 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         SERVER_ADDRESS.split(':').nth(1).unwrap_or_default()
     );
 
+    let idle_timeout = std::env::var(IDLE_TIMEOUT_ENV)
+        .ok()
+        .and_then(|secs| secs.parse().ok())
+        .map(Duration::from_secs);
+    serve(listener, idle_timeout).await;
+    Ok(())
+}
+
+/// Accepts the connections and handles each of them in a separate task.
+///
+/// # Arguments
+///
+/// * `listener` - The TCP listener to accept connections from.
+/// * `idle_timeout` - When set, the server returns after having no active connections for this long.
+///
+async fn serve(listener: TcpListener, idle_timeout: Option<Duration>) {
     let (tx, rx) = mpsc::channel::<(RequestPayload, Sender<BankResponse>)>();
-    create_processing_thread(rx);
-    // listener.set_nonblocking(true).unwrap();
+    let (events, _) = broadcast::channel(EVENT_BUFFER_SIZE);
+    create_processing_thread(rx, events.clone());
+    let active_connections = Arc::new(AtomicUsize::new(0));
+    let last_activity = Arc::new(Mutex::new(Instant::now()));
     loop {
-        if let Some(stream) = try_accept(&listener).await {
+        let accepted = match idle_timeout {
+            Some(idle_timeout) => {
+                match tokio::time::timeout(idle_timeout, try_accept(&listener)).await {
+                    Ok(accepted) => accepted,
+                    Err(_) => {
+                        let idle_for = last_activity.lock().unwrap().elapsed();
+                        if active_connections.load(Ordering::SeqCst) == 0
+                            && idle_for >= idle_timeout
+                        {
+                            info!("Shutting down after being idle for {:?}", idle_for);
+                            return;
+                        }
+                        continue;
+                    }
+                }
+            }
+            None => try_accept(&listener).await,
+        };
+        if let Some(stream) = accepted {
             let tx = tx.clone();
+            let events = events.clone();
+            let active_connections = active_connections.clone();
+            let last_activity = last_activity.clone();
+            active_connections.fetch_add(1, Ordering::SeqCst);
             tokio::spawn(async move {
-                match handle_client_requests(stream, tx).await {
+                match handle_client_requests(stream, tx, events).await {
                     Ok(_) => {}
                     Err(e) => {
                         if !e.to_string().contains("Resource temporarily unavailable") {
@@ -46,6 +129,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                         }
                     }
                 }
+                *last_activity.lock().unwrap() = Instant::now();
+                active_connections.fetch_sub(1, Ordering::SeqCst);
             });
         }
     }
@@ -56,24 +141,55 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 /// # Arguments
 ///
 /// * `channel_connector` - The channel connector that receives requests from other threads.
+/// * `events` - The channel the new operations are published to, see [`RequestPayload::Subscribe`].
 ///
-fn create_processing_thread(chanel_connector: Receiver<(RequestPayload, Sender<BankResponse>)>) {
+fn create_processing_thread(
+    chanel_connector: Receiver<(RequestPayload, Sender<BankResponse>)>,
+    events: broadcast::Sender<Operation>,
+) {
     let mut bank: Bank = Bank::new();
     let _bank_thread = std::thread::spawn(move || loop {
         match chanel_connector.recv() {
             Ok((process, callback_chanel)) => {
+                let history_len = bank.history_len();
                 let res = match process {
                     OpenAccount(OpenAccountRequestParams { account }) => {
                         let trans_id = bank.create_account(account.as_str());
                         callback_chanel.send(Transaction(trans_id))
                     }
-                    Deposit(DepositParams { account, amount }) => {
-                        let trans_id = bank.deposit(account.as_str(), amount);
-                        callback_chanel.send(Transaction(trans_id))
+                    Deposit(DepositParams {
+                        account,
+                        amount,
+                        category,
+                        idempotency_key,
+                    }) => {
+                        let replayed = idempotency_key
+                            .as_deref()
+                            .is_some_and(|key| bank.is_idempotency_key_used(key));
+                        let trans_id = bank.apply_once(idempotency_key, |bank| {
+                            bank.deposit_with_category(account.as_str(), amount, category)
+                        });
+                        callback_chanel.send(match trans_id {
+                            Ok(trans_id) if replayed => BankResponse::Replayed(trans_id),
+                            trans_id => Transaction(trans_id),
+                        })
                     }
-                    Withdraw(WithdrawParams { account, amount }) => {
-                        let trans_id = bank.withdraw(account.as_str(), amount);
-                        callback_chanel.send(Transaction(trans_id))
+                    Withdraw(WithdrawParams {
+                        account,
+                        amount,
+                        category,
+                        idempotency_key,
+                    }) => {
+                        let replayed = idempotency_key
+                            .as_deref()
+                            .is_some_and(|key| bank.is_idempotency_key_used(key));
+                        let trans_id = bank.apply_once(idempotency_key, |bank| {
+                            bank.withdraw_with_category(account.as_str(), amount, category)
+                        });
+                        callback_chanel.send(match trans_id {
+                            Ok(trans_id) if replayed => BankResponse::Replayed(trans_id),
+                            trans_id => Transaction(trans_id),
+                        })
                     }
                     Transfer(TransferParams {
                         sender_account,
@@ -95,15 +211,41 @@ fn create_processing_thread(chanel_connector: Receiver<(RequestPayload, Sender<B
                         let history = bank.get_history();
                         callback_chanel.send(BankResponse::History(history))
                     }
+                    ImportOperations(ImportOperationsParams { operations, .. }) => {
+                        let (applied, skipped) = bank.import_operations(operations.iter());
+                        callback_chanel.send(BankResponse::Imported(applied, skipped))
+                    }
+                    GetOperation(id) => {
+                        let operation = bank.get_operation_by_id(&id).cloned();
+                        callback_chanel.send(BankResponse::Operation(operation))
+                    }
+                    GetHistoryForAccount(account) => {
+                        let history = bank
+                            .get_account_history(account.as_str())
+                            .map(|history| history.into_iter().cloned().collect());
+                        callback_chanel.send(BankResponse::History(history))
+                    }
+                    GetHistoryLen => {
+                        callback_chanel.send(BankResponse::HistoryLen(bank.history_len()))
+                    }
+                    ListAccounts => {
+                        callback_chanel.send(BankResponse::Accounts(bank.list_accounts()))
+                    }
                     _ => Ok(()),
                 };
 
+                let new_operations = bank.history_len().saturating_sub(history_len);
+                for operation in bank.latest_operations(new_operations) {
+                    // there is nobody to notify without subscribers
+                    let _ = events.send(operation.clone());
+                }
                 if res.is_err() {
                     error!("{}", res.err().unwrap());
                 }
             }
             Err(e) => {
-                error!("{}", e);
+                info!("Stopping the processing thread: {}", e);
+                break;
             }
         }
     });
@@ -134,6 +276,91 @@ async fn try_accept(listener: &TcpListener) -> Option<TcpStream> {
     }
 }
 
+/// Checks whether the IO error means the connection can not be used anymore.
+fn is_fatal_io_error(err: &io::Error) -> bool {
+    matches!(
+        err.kind(),
+        io::ErrorKind::ConnectionReset
+            | io::ErrorKind::ConnectionAborted
+            | io::ErrorKind::BrokenPipe
+            | io::ErrorKind::NotConnected
+            | io::ErrorKind::UnexpectedEof
+    )
+}
+
+/// The way a client delimits its requests.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Framing {
+    /// Every JSON request is sent as is, a request split over several frames is put together.
+    Raw,
+    /// Every line holds a JSON request, handy for talking to the server with `nc` or `telnet`.
+    NewlineDelimited,
+}
+
+impl Framing {
+    /// Detects the framing from the bytes received since the connection was opened.
+    ///
+    /// A line break right after the first JSON value, or after malformed input, switches the
+    /// connection to the newline-delimited mode, so a request split over several frames is
+    /// detected once its line is complete.
+    ///
+    /// # Returns
+    ///
+    /// Returns `None` while the first JSON value is incomplete.
+    ///
+    fn detect(received: &[u8]) -> Option<Self> {
+        let mut values = serde_json::Deserializer::from_slice(received).into_iter::<IgnoredAny>();
+        match values.next()? {
+            Ok(_) => {
+                let line_end = received[values.byte_offset()..]
+                    .iter()
+                    .find(|byte| !matches!(byte, b' ' | b'\t' | b'\r'));
+                if line_end == Some(&b'\n') {
+                    Some(Framing::NewlineDelimited)
+                } else {
+                    Some(Framing::Raw)
+                }
+            }
+            Err(err) if err.is_eof() => None,
+            Err(_) if received.contains(&b'\n') => Some(Framing::NewlineDelimited),
+            Err(_) => Some(Framing::Raw),
+        }
+    }
+}
+
+/// Takes the complete lines out of the buffer, an unfinished line stays in it.
+///
+/// # Returns
+///
+/// Returns the non-blank lines without the line breaks.
+///
+fn take_lines(buffer: &mut Vec<u8>) -> Vec<Vec<u8>> {
+    let Some(last_break) = buffer.iter().rposition(|byte| *byte == b'\n') else {
+        return vec![];
+    };
+    let unfinished = buffer.split_off(last_break + 1);
+    let lines = std::mem::replace(buffer, unfinished);
+    lines
+        .split(|byte| *byte == b'\n')
+        .map(|line| line.strip_suffix(b"\r").unwrap_or(line))
+        .filter(|line| !line.iter().all(u8::is_ascii_whitespace))
+        .map(<[u8]>::to_vec)
+        .collect()
+}
+
+/// Sends the response, terminating it with a line break in the newline-delimited mode.
+async fn send_response(
+    stream: &mut TcpStream,
+    resp: &Response,
+    framing: Framing,
+) -> Result<(), std::io::Error> {
+    resp.send(stream).await?;
+    if framing == Framing::NewlineDelimited {
+        stream.write_all(b"\n").await?;
+    }
+    Ok(())
+}
+
 /// Handles a client connection.
 ///
 /// This function takes a mutable reference to a `Bank` object and a `TcpStream` object,
@@ -143,54 +370,272 @@ async fn try_accept(listener: &TcpListener) -> Option<TcpStream> {
 ///
 /// * `stream` - A mutable reference to a `TcpStream` object.
 /// * `processing_sender` - A mutable reference to a `Sender<(RequestPayload, Sender<BankResponse>)>`
+/// * `events` - The channel of the new operations, subscribed to on [`RequestPayload::Subscribe`].
 ///
+/// Malformed frames and failed requests are answered with an error payload and the connection
+/// stays open, only fatal IO errors close it. The requests are read either one per frame or
+/// one per line, see [`Framing`].
 /// ```
 async fn handle_client_requests(
     mut stream: TcpStream,
     processing_sender: Sender<(RequestPayload, Sender<BankResponse>)>,
+    events: broadcast::Sender<Operation>,
 ) -> Result<(), ProcessingErrorsResult> {
+    let mut undo_stack = UndoStack::default();
+    let mut detected_framing = None;
+    let mut pending: Vec<u8> = vec![];
     loop {
-        debug!("waiting for client {:?} , thread : {:?}", stream.peer_addr()?, std::thread::current().id());
-        let mut received: Vec<u8> = vec![];
-        let mut chunk = [0u8; MAX_CHUNK_BYTE_SIZE];
-        loop {
-            let bytes_read = stream.read(&mut chunk).await?;
-            received.extend_from_slice(&chunk[..bytes_read]);
-            if bytes_read < MAX_CHUNK_BYTE_SIZE {
-                break;
+        debug!(
+            "waiting for client {:?} , thread : {:?}",
+            stream.peer_addr()?,
+            std::thread::current().id()
+        );
+        let received = match codec::read_frame(&mut stream).await {
+            Ok(received) => received,
+            Err(err) if is_fatal_io_error(&err) => return Err(err.into()),
+            Err(err) => {
+                error!("Read error: {}", err);
+                let resp = Response {
+                    payload: ResponsePayload::Error(err.to_string()),
+                };
+                let framing = detected_framing.unwrap_or(Framing::Raw);
+                send_response(&mut stream, &resp, framing).await?;
+                continue;
             }
-        }
+        };
         if received.is_empty() {
             return Ok(());
         }
-        let req = serde_json::from_slice::<Request>(received.as_slice());
-
-        if let Err(ref err) = req {
-            let resp = Response {
-                payload: ResponsePayload::DeserializeError(err.to_string()),
+        pending.extend_from_slice(&received);
+        let framing = match detected_framing.or_else(|| Framing::detect(&pending)) {
+            Some(framing) => framing,
+            None if pending.len() <= MAX_MESSAGE_BYTE_SIZE => continue,
+            None => Framing::Raw,
+        };
+        detected_framing = Some(framing);
+        let frames = match framing {
+            Framing::Raw
+                if codec::is_incomplete(&pending) && pending.len() <= MAX_MESSAGE_BYTE_SIZE =>
+            {
+                vec![]
+            }
+            Framing::Raw => vec![std::mem::take(&mut pending)],
+            Framing::NewlineDelimited => take_lines(&mut pending),
+        };
+        for frame in frames {
+            let Some(resp) =
+                process_frame(&mut stream, &frame, &processing_sender, &mut undo_stack).await?
+            else {
+                return Ok(());
             };
-            error!("Deserialize error: {:?}", err);
-            resp.send(&mut stream).await?;
+            if resp.payload == ResponsePayload::Subscribed {
+                // subscribe before the acknowledgement, so no later operation is missed
+                let subscription = events.subscribe();
+                send_response(&mut stream, &resp, framing).await?;
+                return send_events(&mut stream, subscription, framing).await;
+            }
+            debug!("send data to client");
+            send_response(&mut stream, &resp, framing).await?;
         }
-        let req = req.unwrap();
-        let resp = match &req.payload {
-            Ping => process_ping(),
-            OpenAccount(_) => create_account(req.payload, &processing_sender),
-            Deposit(_) => process_deposit(req.payload, &processing_sender),
-            Withdraw(_) => process_withdraw(req.payload, &processing_sender),
-            Transfer(_) => process_transfer(req.payload, &processing_sender),
-            GetBalance(_) => process_get_balance(req.payload, &processing_sender),
-            GetHistory() => process_get_history(req.payload, &processing_sender),
-            GetHistoryForAccount(_) => process_history_for_account(req.payload, &processing_sender),
-            CloseConnection => {
-                info!("Closing connection with {}", stream.peer_addr()?);
-                stream.shutdown().await?;
-                return Ok(());
+    }
+}
+
+/// Pushes every new operation to the subscribed client until it disconnects.
+///
+/// # Arguments
+///
+/// * `stream` - A mutable reference to a `TcpStream` object.
+/// * `events` - The receiver of the new operations.
+/// * `framing` - The framing of the connection.
+///
+/// The requests sent by a subscribed client are ignored.
+///
+async fn send_events(
+    stream: &mut TcpStream,
+    mut events: broadcast::Receiver<Operation>,
+    framing: Framing,
+) -> Result<(), ProcessingErrorsResult> {
+    info!("Streaming events to {}", stream.peer_addr()?);
+    loop {
+        tokio::select! {
+            event = events.recv() => {
+                let payload = match event {
+                    Ok(operation) => ResponsePayload::Event(operation),
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        error!("Subscriber lagged behind by {} events", skipped);
+                        ResponsePayload::Error(format!("{} events were skipped", skipped))
+                    }
+                    Err(broadcast::error::RecvError::Closed) => return Ok(()),
+                };
+                send_response(stream, &Response { payload }, framing).await?;
+            }
+            received = codec::read_frame(stream) => {
+                if received?.is_empty() {
+                    return Ok(());
+                }
+                debug!("ignoring a request of the subscribed client");
             }
-        }?;
-        debug!("send data to client");
-        resp.send(&mut stream).await?;
+        }
+    }
+}
+
+/// Processes a single request frame.
+///
+/// # Arguments
+///
+/// * `stream` - A mutable reference to a `TcpStream` object.
+/// * `frame` - The bytes of the request.
+/// * `processing_sender` - The sender for sending the request to the processing thread.
+/// * `undo_stack` - The reverse operations of the connection.
+///
+/// # Returns
+///
+/// Returns the response for the client, or `None` when the client has closed the connection.
+///
+async fn process_frame(
+    stream: &mut TcpStream,
+    frame: &[u8],
+    processing_sender: &Sender<(RequestPayload, Sender<BankResponse>)>,
+    undo_stack: &mut UndoStack,
+) -> Result<Option<Response>, ProcessingErrorsResult> {
+    let req = match serde_json::from_slice::<Request>(frame) {
+        Ok(req) => req,
+        Err(err) => {
+            error!("Deserialize error: {:?}", err);
+            return Ok(Some(Response {
+                payload: deserialize_error(frame, &err),
+            }));
+        }
+    };
+    if matches!(req.payload, CloseConnection) {
+        info!("Closing connection with {}", stream.peer_addr()?);
+        stream.shutdown().await?;
+        return Ok(None);
     }
+    Ok(Some(process_request(
+        req.payload,
+        processing_sender,
+        undo_stack,
+    )))
+}
+
+/// The number of bytes around the error position included into the snippet.
+const ERROR_SNIPPET_RADIUS: usize = 16;
+
+/// Describes the deserialize error with its position in the frame and the bytes around it.
+fn deserialize_error(frame: &[u8], err: &serde_json::Error) -> ResponsePayload {
+    let line_start = frame
+        .split_inclusive(|byte| *byte == b'\n')
+        .take(err.line().saturating_sub(1))
+        .map(<[u8]>::len)
+        .sum::<usize>();
+    let offset = (line_start + err.column().saturating_sub(1)).min(frame.len());
+    let snippet_range = offset.saturating_sub(ERROR_SNIPPET_RADIUS)
+        ..(offset + ERROR_SNIPPET_RADIUS).min(frame.len());
+    ResponsePayload::DeserializeError {
+        message: err.to_string(),
+        line: err.line(),
+        column: err.column(),
+        offset,
+        snippet: String::from_utf8_lossy(&frame[snippet_range]).into_owned(),
+    }
+}
+
+/// Processes a single request, failed requests are answered with an error payload.
+///
+/// # Arguments
+///
+/// * `payload` - The request payload.
+/// * `processing_sender` - The sender for sending the request to the processing thread.
+/// * `undo_stack` - The reverse operations of the connection.
+///
+/// # Returns
+///
+/// Returns the response for the client.
+///
+fn process_request(
+    payload: RequestPayload,
+    processing_sender: &Sender<(RequestPayload, Sender<BankResponse>)>,
+    undo_stack: &mut UndoStack,
+) -> Response {
+    let mut reverse = reverse_operation(&payload);
+    let resp = match &payload {
+        Ping => process_ping(),
+        Capabilities => process_capabilities(),
+        Subscribe => Ok(Response {
+            payload: ResponsePayload::Subscribed,
+        }),
+        OpenAccount(_) => create_account(payload, processing_sender),
+        Deposit(_) => process_deposit(payload, processing_sender, &mut reverse),
+        Withdraw(_) => process_withdraw(payload, processing_sender, &mut reverse),
+        Transfer(_) => process_transfer(payload, processing_sender),
+        GetBalance(_) => process_get_balance(payload, processing_sender),
+        GetHistory() => process_get_history(payload, processing_sender),
+        GetHistoryForAccount(_) => process_history_for_account(payload, processing_sender),
+        GetHistoryLen => process_get_history_len(payload, processing_sender),
+        ListAccounts => process_list_accounts(payload, processing_sender),
+        GetOperation(_) => process_get_operation(payload, processing_sender),
+        ImportOperations(_) => process_import_operations(payload, processing_sender),
+        Undo => process_undo(undo_stack, processing_sender),
+        Batch(_) => process_batch(payload, processing_sender, undo_stack),
+        CloseConnection => Ok(Response {
+            payload: ResponsePayload::Error("Can not close the connection here".to_string()),
+        }),
+    };
+    if let (
+        Some(reverse),
+        Ok(Response {
+            payload:
+                ResponsePayload::DepositSuccess(_)
+                | ResponsePayload::WithdrawSuccess(_)
+                | ResponsePayload::TransferSuccess(_),
+        }),
+    ) = (reverse, &resp)
+    {
+        undo_stack.push(reverse);
+    }
+    resp.unwrap_or_else(|err| {
+        error!("Processing error: {}", err);
+        Response {
+            payload: ResponsePayload::Error(err.to_string()),
+        }
+    })
+}
+
+/// Processes the requests of a batch in order within a single round-trip.
+///
+/// # Arguments
+///
+/// * `batch_payload` - The request payload containing the requests.
+/// * `processing_sender` - The sender for sending the requests to the processing thread.
+/// * `undo_stack` - The reverse operations of the connection.
+///
+/// # Returns
+///
+/// Returns a `ResponseResult` with a response payload for every request, nested batches and
+/// closing the connection are answered with an error payload.
+///
+fn process_batch(
+    batch_payload: RequestPayload,
+    processing_sender: &Sender<(RequestPayload, Sender<BankResponse>)>,
+    undo_stack: &mut UndoStack,
+) -> ResponseResult {
+    info!("process batch {batch_payload:?}");
+    let Batch(requests) = batch_payload else {
+        return Err(TypeMismatchError("Expected Batch".to_string()));
+    };
+    let responses = requests
+        .into_iter()
+        .map(|payload| match payload {
+            Batch(_) | CloseConnection | Subscribe => {
+                ResponsePayload::Error("Request is not allowed in a batch".to_string())
+            }
+            payload => process_request(payload, processing_sender, undo_stack).payload,
+        })
+        .collect();
+    Ok(Response {
+        payload: ResponsePayload::Batch(responses),
+    })
 }
 
 /// Creates a new account by processing the given request payload and sending it to the processing thread.
@@ -233,6 +678,7 @@ fn create_account(
 ///
 /// * `deposit_params` - The request payload containing the deposit information.
 /// * `processing_sender` - The sender for sending the deposit request to the processing thread.
+/// * `reverse` - The reverse operation of the deposit, dropped when a replayed deposit applies nothing.
 ///
 /// # Returns
 ///
@@ -241,10 +687,17 @@ fn create_account(
 fn process_deposit(
     deposit_params: RequestPayload,
     processing_sender: &Sender<(RequestPayload, Sender<BankResponse>)>,
+    reverse: &mut Option<RequestPayload>,
 ) -> ResponseResult {
     info!("process deposit for {:?}", deposit_params);
     let processing_response = processing(deposit_params, processing_sender)?;
 
+    if let BankResponse::Replayed(trans_id) = processing_response {
+        *reverse = None;
+        return Ok(Response {
+            payload: ResponsePayload::DepositSuccess(trans_id),
+        });
+    }
     if let Transaction(result) = processing_response {
         return match result {
             Ok(trans_id) => Ok(Response {
@@ -264,6 +717,7 @@ fn process_deposit(
 ///
 /// * `withdraw_payload` - The request payload containing the withdrawal information.
 /// * `processing_sender` - The sender for sending the withdrawal request to the processing thread.
+/// * `reverse` - The reverse operation of the withdrawal, dropped when a replayed withdrawal applies nothing.
 ///
 /// # Returns
 ///
@@ -272,10 +726,17 @@ fn process_deposit(
 fn process_withdraw(
     withdraw_payload: RequestPayload,
     processing_sender: &Sender<(RequestPayload, Sender<BankResponse>)>,
+    reverse: &mut Option<RequestPayload>,
 ) -> ResponseResult {
     info!("process withdraw for account {:?}", withdraw_payload);
 
     let processing_response = processing(withdraw_payload, processing_sender)?;
+    if let BankResponse::Replayed(trans_id) = processing_response {
+        *reverse = None;
+        return Ok(Response {
+            payload: ResponsePayload::WithdrawSuccess(trans_id),
+        });
+    }
     if let Transaction(result) = processing_response {
         return match result {
             Ok(trans_id) => Ok(Response {
@@ -359,7 +820,7 @@ fn process_get_balance(
     if let BankResponse::Balance(result) = processing_response {
         return match result {
             Ok(balance) => Ok(Response {
-                payload: ResponsePayload::Balance(balance),
+                payload: ResponsePayload::Balance(balance.to_f64()),
             }),
             Err(error_message) => Ok(Response {
                 payload: ResponsePayload::Error(error_message.to_string()),
@@ -402,6 +863,129 @@ fn process_get_history(
     Err(TypeMismatchError("Expected Transaction".to_string()))
 }
 
+/// Checks the token against the admin token configured on the server.
+fn is_admin_token(token: &str) -> bool {
+    std::env::var(ADMIN_TOKEN_ENV)
+        .is_ok_and(|admin_token| !admin_token.is_empty() && admin_token == token)
+}
+
+/// Processes an import request by sending the operations to the processing thread.
+///
+/// # Arguments
+///
+/// * `import_payload` - The request payload containing the admin token and the operations.
+/// * `processing_sender` - The sender for sending the operations to the processing thread.
+///
+/// # Returns
+///
+/// Returns a `ResponseResult` with the number of applied operations and the ids of the skipped ones,
+/// or an error payload when the admin token does not match.
+///
+fn process_import_operations(
+    import_payload: RequestPayload,
+    processing_sender: &Sender<(RequestPayload, Sender<BankResponse>)>,
+) -> ResponseResult {
+    if let ImportOperations(ImportOperationsParams { admin_token, .. }) = &import_payload {
+        if !is_admin_token(admin_token) {
+            error!("Import rejected: invalid admin token");
+            return Ok(Response {
+                payload: ResponsePayload::Error("Invalid admin token".to_string()),
+            });
+        }
+    }
+    info!("process import operations");
+
+    if let BankResponse::Imported(applied, skipped) = processing(import_payload, processing_sender)?
+    {
+        return Ok(Response {
+            payload: ResponsePayload::OperationsImported { applied, skipped },
+        });
+    };
+
+    Err(TypeMismatchError("Expected Imported".to_string()))
+}
+
+/// Processes an operation request by sending it to the processing thread and handling the response.
+///
+/// # Arguments
+///
+/// * `operation_req_payload` - The request payload containing the transaction identifier.
+/// * `processing_sender` - The sender for sending the request to the processing thread.
+///
+/// # Returns
+///
+/// Returns a `ResponseResult` with the operation, if it is in the history.
+///
+fn process_get_operation(
+    operation_req_payload: RequestPayload,
+    processing_sender: &Sender<(RequestPayload, Sender<BankResponse>)>,
+) -> ResponseResult {
+    info!("process operation {operation_req_payload:?}");
+
+    if let BankResponse::Operation(operation) =
+        processing(operation_req_payload, processing_sender)?
+    {
+        return Ok(Response {
+            payload: ResponsePayload::Operation(operation),
+        });
+    };
+
+    Err(TypeMismatchError("Expected Operation".to_string()))
+}
+
+/// Processes a history length request by sending it to the processing thread and handling the response.
+///
+/// # Arguments
+///
+/// * `history_len_payload` - The request payload for the history length.
+/// * `processing_sender` - The sender for sending the request to the processing thread.
+///
+/// # Returns
+///
+/// Returns a `ResponseResult` with the number of operations in the history.
+///
+fn process_get_history_len(
+    history_len_payload: RequestPayload,
+    processing_sender: &Sender<(RequestPayload, Sender<BankResponse>)>,
+) -> ResponseResult {
+    info!("process history length");
+
+    if let BankResponse::HistoryLen(len) = processing(history_len_payload, processing_sender)? {
+        return Ok(Response {
+            payload: ResponsePayload::HistoryLen(len),
+        });
+    };
+
+    Err(TypeMismatchError("Expected HistoryLen".to_string()))
+}
+
+/// Processes an account listing request by sending it to the processing thread and handling the response.
+///
+/// # Arguments
+///
+/// * `list_accounts_payload` - The request payload for the account listing.
+/// * `processing_sender` - The sender for sending the request to the processing thread.
+///
+/// # Returns
+///
+/// Returns a `ResponseResult` with the codes of the open accounts.
+///
+fn process_list_accounts(
+    list_accounts_payload: RequestPayload,
+    processing_sender: &Sender<(RequestPayload, Sender<BankResponse>)>,
+) -> ResponseResult {
+    info!("process list accounts");
+
+    if let BankResponse::Accounts(accounts) = processing(list_accounts_payload, processing_sender)?
+    {
+        return Ok(Response {
+            payload: ResponsePayload::AccountList(accounts),
+        });
+    };
+
+    Err(TypeMismatchError("Expected Accounts".to_string()))
+}
+
 /// Processes a history request for a specific account by sending it to the processing thread and handling the response.
 ///
 /// # Arguments
@@ -431,6 +1015,88 @@ fn process_history_for_account(
     Err(TypeMismatchError("Expected Transaction".to_string()))
 }
 
+/// Builds the operation that reverses the given one.
+///
+/// # Returns
+///
+/// Returns `None` for requests that do not change balances and can not be undone.
+///
+fn reverse_operation(payload: &RequestPayload) -> Option<RequestPayload> {
+    match payload {
+        Deposit(DepositParams {
+            account,
+            amount,
+            category,
+            ..
+        }) => Some(Withdraw(WithdrawParams {
+            account: account.clone(),
+            amount: *amount,
+            category: category.clone(),
+            idempotency_key: None,
+        })),
+        Withdraw(WithdrawParams {
+            account,
+            amount,
+            category,
+            ..
+        }) => Some(Deposit(DepositParams {
+            account: account.clone(),
+            amount: *amount,
+            category: category.clone(),
+            idempotency_key: None,
+        })),
+        Transfer(TransferParams {
+            sender_account,
+            receiver_account,
+            amount,
+        }) => Some(Transfer(TransferParams {
+            sender_account: receiver_account.clone(),
+            receiver_account: sender_account.clone(),
+            amount: *amount,
+        })),
+        _ => None,
+    }
+}
+
+/// Reverses the most recent operation of the connection by sending the reverse one to the processing thread.
+///
+/// # Arguments
+///
+/// * `undo_stack` - The reverse operations of the connection.
+/// * `processing_sender` - The sender for sending the reverse operation to the processing thread.
+///
+/// # Returns
+///
+/// Returns a `ResponseResult` with an error payload when there is nothing to undo,
+/// the failed reverse operation stays on the stack.
+///
+fn process_undo(
+    undo_stack: &mut UndoStack,
+    processing_sender: &Sender<(RequestPayload, Sender<BankResponse>)>,
+) -> ResponseResult {
+    let Some(reverse) = undo_stack.pop() else {
+        return Ok(Response {
+            payload: ResponsePayload::Error("Nothing to undo".to_string()),
+        });
+    };
+    info!("process undo with {:?}", reverse);
+
+    if let Transaction(result) = processing(reverse.clone(), processing_sender)? {
+        return match result {
+            Ok(trans_id) => Ok(Response {
+                payload: ResponsePayload::UndoSuccess(trans_id),
+            }),
+            Err(error_message) => {
+                undo_stack.push(reverse);
+                Ok(Response {
+                    payload: ResponsePayload::Error(error_message.to_string()),
+                })
+            }
+        };
+    };
+    Err(TypeMismatchError("Expected Transaction".to_string()))
+}
+
 /// Processes a request by sending it to the processing thread and receiving the response.
 ///
 /// # Arguments
@@ -466,3 +1132,573 @@ fn process_ping() -> ResponseResult {
         payload: ResponsePayload::HandShakeEstablished,
     })
 }
+
+/// Processes the capabilities request.
+///
+/// # Returns
+///
+/// Returns the schema version with the names of the requests the server handles.
+///
+fn process_capabilities() -> ResponseResult {
+    debug!("reporting capabilities");
+    Ok(Response {
+        payload: ResponsePayload::Capabilities {
+            version: SCHEMA_VERSION,
+            supported_requests: RequestPayload::VARIANT_NAMES
+                .iter()
+                .map(|name| name.to_string())
+                .collect(),
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bank_engine::bank::{Operation, OperationType};
+    use tokio::io::{AsyncBufReadExt, BufReader};
+
+    /// Starts the bank processing thread and serves a single client connection.
+    async fn spawn_server() -> std::net::SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (tx, rx) = mpsc::channel::<(RequestPayload, Sender<BankResponse>)>();
+        let (events, _) = broadcast::channel(EVENT_BUFFER_SIZE);
+        create_processing_thread(rx, events.clone());
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            handle_client_requests(stream, tx, events).await.unwrap();
+        });
+        addr
+    }
+
+    async fn send_request(stream: &mut TcpStream, payload: RequestPayload) -> ResponsePayload {
+        Request { payload }.send(stream).await.unwrap();
+        Response::new(stream).await.unwrap().payload
+    }
+
+    #[tokio::test]
+    async fn test_bad_frame_keeps_connection() {
+        let addr = spawn_server().await;
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+
+        stream.write_all(b"not a request").await.unwrap();
+        let resp = Response::new(&mut stream).await.unwrap();
+        assert!(matches!(
+            resp.payload,
+            ResponsePayload::DeserializeError { .. }
+        ));
+
+        let resp = send_request(
+            &mut stream,
+            OpenAccount(OpenAccountRequestParams {
+                account: "Alice".to_string(),
+            }),
+        )
+        .await;
+        assert!(matches!(resp, ResponsePayload::AccountCreated(_)));
+
+        let resp = send_request(
+            &mut stream,
+            Deposit(DepositParams {
+                account: "Alice".to_string(),
+                amount: 100.0,
+                category: None,
+                idempotency_key: None,
+            }),
+        )
+        .await;
+        assert!(matches!(resp, ResponsePayload::DepositSuccess(_)));
+
+        stream.write_all(b"{\"payload\": }").await.unwrap();
+        let resp = Response::new(&mut stream).await.unwrap();
+        assert!(matches!(
+            resp.payload,
+            ResponsePayload::DeserializeError { .. }
+        ));
+
+        let resp = send_request(
+            &mut stream,
+            GetBalance(GetBalanceAccountRequestParams {
+                account: "Alice".to_string(),
+            }),
+        )
+        .await;
+        assert_eq!(resp, ResponsePayload::Balance(100.0));
+    }
+
+    #[tokio::test]
+    async fn test_deserialize_error_position() {
+        let addr = spawn_server().await;
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+
+        stream
+            .write_all(b"{\"payload\":\n  \"Pong\"}")
+            .await
+            .unwrap();
+        let resp = Response::new(&mut stream).await.unwrap();
+        let ResponsePayload::DeserializeError {
+            line,
+            column,
+            offset,
+            snippet,
+            ..
+        } = resp.payload
+        else {
+            panic!("Unexpected response {:?}", resp.payload);
+        };
+        assert_eq!(line, 2);
+        assert!(column > 0);
+        assert_eq!(offset, 12 + column - 1);
+        assert!(snippet.contains("Pong"));
+    }
+
+    #[tokio::test]
+    async fn test_undo_deposit() {
+        let addr = spawn_server().await;
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+
+        let resp = send_request(&mut stream, Undo).await;
+        assert!(matches!(resp, ResponsePayload::Error(_)));
+
+        send_request(
+            &mut stream,
+            OpenAccount(OpenAccountRequestParams {
+                account: "Bob".to_string(),
+            }),
+        )
+        .await;
+        let resp = send_request(
+            &mut stream,
+            Deposit(DepositParams {
+                account: "Bob".to_string(),
+                amount: 100.0,
+                category: None,
+                idempotency_key: None,
+            }),
+        )
+        .await;
+        assert!(matches!(resp, ResponsePayload::DepositSuccess(_)));
+
+        let resp = send_request(&mut stream, Undo).await;
+        assert!(matches!(resp, ResponsePayload::UndoSuccess(_)));
+
+        let resp = send_request(
+            &mut stream,
+            GetBalance(GetBalanceAccountRequestParams {
+                account: "Bob".to_string(),
+            }),
+        )
+        .await;
+        assert_eq!(resp, ResponsePayload::Balance(0.0));
+
+        let resp = send_request(&mut stream, Undo).await;
+        assert!(matches!(resp, ResponsePayload::Error(_)));
+    }
+
+    #[tokio::test]
+    async fn test_newline_delimited_requests() {
+        let addr = spawn_server().await;
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+
+        let mut lines = String::new();
+        for payload in [
+            Ping,
+            OpenAccount(OpenAccountRequestParams {
+                account: "Carol".to_string(),
+            }),
+        ] {
+            lines.push_str(&serde_json::to_string(&Request { payload }).unwrap());
+            lines.push('\n');
+        }
+        stream.write_all(lines.as_bytes()).await.unwrap();
+
+        let mut responses = BufReader::new(stream).lines();
+        let resp = responses.next_line().await.unwrap().unwrap();
+        let resp = serde_json::from_str::<Response>(&resp).unwrap();
+        assert_eq!(resp.payload, ResponsePayload::HandShakeEstablished);
+        let resp = responses.next_line().await.unwrap().unwrap();
+        let resp = serde_json::from_str::<Response>(&resp).unwrap();
+        assert!(matches!(resp.payload, ResponsePayload::AccountCreated(_)));
+    }
+
+    #[tokio::test]
+    async fn test_newline_delimited_request_split() {
+        let addr = spawn_server().await;
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        stream.set_nodelay(true).unwrap();
+
+        let line = serde_json::to_string(&Request { payload: Ping }).unwrap();
+        let (start, end) = line.split_at(line.len() / 2);
+        stream.write_all(start.as_bytes()).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        stream
+            .write_all(format!("{end}\n{line}\n").as_bytes())
+            .await
+            .unwrap();
+
+        let mut responses = BufReader::new(stream).lines();
+        for _ in 0..2 {
+            let resp = responses.next_line().await.unwrap().unwrap();
+            let resp = serde_json::from_str::<Response>(&resp).unwrap();
+            assert_eq!(resp.payload, ResponsePayload::HandShakeEstablished);
+        }
+    }
+
+    #[test]
+    fn test_detect_framing() {
+        assert_eq!(Framing::detect(b"{\"payload\":"), None);
+        assert_eq!(Framing::detect(b" \n"), None);
+        assert_eq!(
+            Framing::detect(b"{\"payload\":\"Ping\"}\r\n"),
+            Some(Framing::NewlineDelimited)
+        );
+        assert_eq!(
+            Framing::detect(b"not a request\n"),
+            Some(Framing::NewlineDelimited)
+        );
+        assert_eq!(
+            Framing::detect(b"{\"payload\":\n  \"Ping\"}"),
+            Some(Framing::Raw)
+        );
+        assert_eq!(Framing::detect(b"not a request"), Some(Framing::Raw));
+    }
+
+    #[test]
+    fn test_take_lines() {
+        let mut buffer = b"{\"a\":1}\r\n\n{\"b\":2}\n{\"c\"".to_vec();
+        assert_eq!(
+            take_lines(&mut buffer),
+            vec![b"{\"a\":1}".to_vec(), b"{\"b\":2}".to_vec()]
+        );
+        assert_eq!(buffer, b"{\"c\"".to_vec());
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_receives_events() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(serve(listener, None));
+
+        let mut subscriber = TcpStream::connect(addr).await.unwrap();
+        assert_eq!(
+            send_request(&mut subscriber, Subscribe).await,
+            ResponsePayload::Subscribed
+        );
+
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        let resp = send_request(
+            &mut stream,
+            OpenAccount(OpenAccountRequestParams {
+                account: "Dave".to_string(),
+            }),
+        )
+        .await;
+        let ResponsePayload::AccountCreated(id) = resp else {
+            panic!("Unexpected response {:?}", resp);
+        };
+
+        let event = Response::new(&mut subscriber).await.unwrap().payload;
+        assert_eq!(
+            event,
+            ResponsePayload::Event(Operation::new(
+                id,
+                "Dave",
+                0.0,
+                OperationType::CreateAccount
+            ))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_capabilities() {
+        let addr = spawn_server().await;
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+
+        let ResponsePayload::Capabilities {
+            version,
+            supported_requests,
+        } = send_request(&mut stream, Capabilities).await
+        else {
+            panic!("Expected capabilities");
+        };
+        assert_eq!(version, SCHEMA_VERSION);
+        for request in [
+            "OpenAccount",
+            "Deposit",
+            "Withdraw",
+            "Transfer",
+            "GetBalance",
+        ] {
+            assert!(
+                supported_requests.contains(&request.to_string()),
+                "{request} is not supported"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_list_accounts() {
+        let addr = spawn_server().await;
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+
+        for account in ["Zoe", "Frank"] {
+            send_request(
+                &mut stream,
+                OpenAccount(OpenAccountRequestParams {
+                    account: account.to_string(),
+                }),
+            )
+            .await;
+        }
+        assert_eq!(
+            send_request(&mut stream, ListAccounts).await,
+            ResponsePayload::AccountList(vec!["Frank".to_string(), "Zoe".to_string()])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_deposit_idempotency_key() {
+        let addr = spawn_server().await;
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+
+        send_request(
+            &mut stream,
+            OpenAccount(OpenAccountRequestParams {
+                account: "Grace".to_string(),
+            }),
+        )
+        .await;
+        let deposit = Deposit(DepositParams {
+            account: "Grace".to_string(),
+            amount: 100.0,
+            category: None,
+            idempotency_key: Some("retry-1".to_string()),
+        });
+        let first = send_request(&mut stream, deposit.clone()).await;
+        let retry = send_request(&mut stream, deposit).await;
+        assert!(matches!(first, ResponsePayload::DepositSuccess(_)));
+        assert_eq!(first, retry);
+
+        let resp = send_request(
+            &mut stream,
+            GetBalance(GetBalanceAccountRequestParams {
+                account: "Grace".to_string(),
+            }),
+        )
+        .await;
+        assert_eq!(resp, ResponsePayload::Balance(100.0));
+    }
+
+    #[tokio::test]
+    async fn test_undo_replayed_deposit() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(serve(listener, None));
+
+        // the funds deposited on another connection can not be undone on this one
+        let mut other = TcpStream::connect(addr).await.unwrap();
+        send_request(
+            &mut other,
+            OpenAccount(OpenAccountRequestParams {
+                account: "Heidi".to_string(),
+            }),
+        )
+        .await;
+        send_request(
+            &mut other,
+            Deposit(DepositParams {
+                account: "Heidi".to_string(),
+                amount: 500.0,
+                category: None,
+                idempotency_key: None,
+            }),
+        )
+        .await;
+
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        let deposit = Deposit(DepositParams {
+            account: "Heidi".to_string(),
+            amount: 100.0,
+            category: None,
+            idempotency_key: Some("retry-2".to_string()),
+        });
+        let first = send_request(&mut stream, deposit.clone()).await;
+        let retry = send_request(&mut stream, deposit).await;
+        assert!(matches!(first, ResponsePayload::DepositSuccess(_)));
+        assert_eq!(first, retry);
+
+        let resp = send_request(&mut stream, Undo).await;
+        assert!(matches!(resp, ResponsePayload::UndoSuccess(_)));
+        let resp = send_request(&mut stream, Undo).await;
+        assert!(matches!(resp, ResponsePayload::Error(_)));
+
+        let resp = send_request(
+            &mut stream,
+            GetBalance(GetBalanceAccountRequestParams {
+                account: "Heidi".to_string(),
+            }),
+        )
+        .await;
+        assert_eq!(resp, ResponsePayload::Balance(500.0));
+    }
+
+    #[tokio::test]
+    async fn test_history_len() {
+        let addr = spawn_server().await;
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+
+        assert_eq!(
+            send_request(&mut stream, GetHistoryLen).await,
+            ResponsePayload::HistoryLen(0)
+        );
+        send_request(
+            &mut stream,
+            OpenAccount(OpenAccountRequestParams {
+                account: "Dave".to_string(),
+            }),
+        )
+        .await;
+        for _ in 0..2 {
+            send_request(
+                &mut stream,
+                Deposit(DepositParams {
+                    account: "Dave".to_string(),
+                    amount: 10.0,
+                    category: None,
+                    idempotency_key: None,
+                }),
+            )
+            .await;
+        }
+        assert_eq!(
+            send_request(&mut stream, GetHistoryLen).await,
+            ResponsePayload::HistoryLen(3)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_import_operations() {
+        std::env::set_var(ADMIN_TOKEN_ENV, "secret");
+        let addr = spawn_server().await;
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        let operations = vec![
+            Operation::new("1".to_string(), "Erin", 0.0, OperationType::CreateAccount),
+            Operation::new("2".to_string(), "Erin", 100.0, OperationType::Deposit),
+            Operation::new("3".to_string(), "Erin", 500.0, OperationType::Withdraw),
+        ];
+
+        let resp = send_request(
+            &mut stream,
+            ImportOperations(ImportOperationsParams {
+                admin_token: "wrong".to_string(),
+                operations: operations.clone(),
+            }),
+        )
+        .await;
+        assert!(matches!(resp, ResponsePayload::Error(_)));
+
+        let resp = send_request(
+            &mut stream,
+            ImportOperations(ImportOperationsParams {
+                admin_token: "secret".to_string(),
+                operations,
+            }),
+        )
+        .await;
+        assert_eq!(
+            resp,
+            ResponsePayload::OperationsImported {
+                applied: 2,
+                skipped: vec!["3".to_string()],
+            }
+        );
+
+        let resp = send_request(
+            &mut stream,
+            GetBalance(GetBalanceAccountRequestParams {
+                account: "Erin".to_string(),
+            }),
+        )
+        .await;
+        assert_eq!(resp, ResponsePayload::Balance(100.0));
+    }
+
+    #[tokio::test]
+    async fn test_idle_server_exits() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(serve(listener, Some(Duration::from_millis(200))));
+
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        assert_eq!(
+            send_request(&mut stream, Ping).await,
+            ResponsePayload::HandShakeEstablished
+        );
+        drop(stream);
+
+        tokio::time::timeout(Duration::from_secs(5), server)
+            .await
+            .expect("the idle server did not exit")
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_batch_transfer_with_histories() {
+        let addr = spawn_server().await;
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        for account in ["Frank", "Grace"] {
+            send_request(
+                &mut stream,
+                OpenAccount(OpenAccountRequestParams {
+                    account: account.to_string(),
+                }),
+            )
+            .await;
+        }
+        send_request(
+            &mut stream,
+            Deposit(DepositParams {
+                account: "Frank".to_string(),
+                amount: 100.0,
+                category: None,
+                idempotency_key: None,
+            }),
+        )
+        .await;
+
+        let resp = send_request(
+            &mut stream,
+            Batch(vec![
+                Transfer(TransferParams {
+                    sender_account: "Frank".to_string(),
+                    receiver_account: "Grace".to_string(),
+                    amount: 40.0,
+                }),
+                GetHistoryForAccount("Frank".to_string()),
+                GetHistoryForAccount("Grace".to_string()),
+                CloseConnection,
+            ]),
+        )
+        .await;
+
+        let ResponsePayload::Batch(payloads) = resp else {
+            panic!("Unexpected response: {:?}", resp);
+        };
+        let [ResponsePayload::TransferSuccess(id), ResponsePayload::History(sender_history), ResponsePayload::History(receiver_history), ResponsePayload::Error(_)] =
+            payloads.as_slice()
+        else {
+            panic!("Unexpected batch: {:?}", payloads);
+        };
+        let transfer = Operation::new(
+            id.to_owned(),
+            "Frank",
+            40.0,
+            OperationType::Transfer {
+                target_account: "Grace".to_string(),
+            },
+        );
+        assert!(sender_history.contains(&transfer));
+        assert!(receiver_history.contains(&transfer));
+    }
+}
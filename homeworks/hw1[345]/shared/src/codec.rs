@@ -0,0 +1,144 @@
+//! Reading and writing of the JSON messages exchanged by the client and the server.
+use crate::constants::{MAX_CHUNK_BYTE_SIZE, MAX_MESSAGE_BYTE_SIZE};
+use serde::de::{DeserializeOwned, IgnoredAny};
+use serde::Serialize;
+use std::io;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/// Reads the bytes available in a single read of at most `MAX_CHUNK_BYTE_SIZE` bytes.
+///
+/// A message may span several frames, the caller accumulates them until the message is complete.
+///
+/// # Returns
+///
+/// Returns the received bytes, an empty frame means the peer has closed the connection.
+///
+pub async fn read_frame<S: AsyncRead + Unpin>(stream: &mut S) -> io::Result<Vec<u8>> {
+    let mut chunk = [0u8; MAX_CHUNK_BYTE_SIZE];
+    let bytes_read = stream.read(&mut chunk).await?;
+    Ok(chunk[..bytes_read].to_vec())
+}
+
+/// Checks whether the bytes are the beginning of a JSON value that has not been fully received.
+pub fn is_incomplete(bytes: &[u8]) -> bool {
+    matches!(serde_json::from_slice::<IgnoredAny>(bytes), Err(err) if err.is_eof())
+}
+
+/// Reads a JSON message, reading further frames while the message is incomplete.
+///
+/// # Errors
+///
+/// Returns `UnexpectedEof` if the connection is closed before the message is complete,
+/// and `InvalidData` if the message can not be deserialized or grows over `MAX_MESSAGE_BYTE_SIZE`.
+///
+pub async fn read_message<T: DeserializeOwned, S: AsyncRead + Unpin>(
+    stream: &mut S,
+) -> io::Result<T> {
+    let mut received: Vec<u8> = vec![];
+    loop {
+        let frame = read_frame(stream).await?;
+        if frame.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "connection closed before the message was complete",
+            ));
+        }
+        received.extend_from_slice(&frame);
+        if received.len() > MAX_MESSAGE_BYTE_SIZE {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("message exceeds {} bytes", MAX_MESSAGE_BYTE_SIZE),
+            ));
+        }
+        if !is_incomplete(&received) {
+            return Ok(serde_json::from_slice(&received)?);
+        }
+    }
+}
+
+/// Writes the message as JSON.
+pub async fn write_message<T: Serialize, S: AsyncWrite + Unpin>(
+    stream: &mut S,
+    message: &T,
+) -> io::Result<()> {
+    let json = serde_json::to_vec(message)?;
+    stream.write_all(&json).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Request, RequestPayload, Response, ResponsePayload};
+
+    #[tokio::test]
+    async fn test_round_trip_tiny_message() {
+        let (mut client, mut server) = tokio::io::duplex(64);
+        let request = Request {
+            payload: RequestPayload::Ping,
+        };
+
+        write_message(&mut client, &request).await.unwrap();
+        let received = read_message::<Request, _>(&mut server).await.unwrap();
+        assert!(matches!(received.payload, RequestPayload::Ping));
+    }
+
+    #[tokio::test]
+    async fn test_round_trip_large_message() {
+        let (mut client, mut server) = tokio::io::duplex(MAX_CHUNK_BYTE_SIZE);
+        let response = Response {
+            payload: ResponsePayload::Error("x".repeat(10 * MAX_CHUNK_BYTE_SIZE)),
+        };
+
+        let writer = tokio::spawn(async move {
+            write_message(&mut client, &response).await.unwrap();
+            response
+        });
+        let received = read_message::<Response, _>(&mut server).await.unwrap();
+        assert_eq!(received.payload, writer.await.unwrap().payload);
+    }
+
+    #[tokio::test]
+    async fn test_round_trip_chunk_sized_message() {
+        let (mut client, mut server) = tokio::io::duplex(2 * MAX_CHUNK_BYTE_SIZE);
+        let padding = MAX_CHUNK_BYTE_SIZE
+            - serde_json::to_vec(&Response {
+                payload: ResponsePayload::Error(String::new()),
+            })
+            .unwrap()
+            .len();
+        let response = Response {
+            payload: ResponsePayload::Error("x".repeat(padding)),
+        };
+        assert_eq!(
+            serde_json::to_vec(&response).unwrap().len(),
+            MAX_CHUNK_BYTE_SIZE
+        );
+
+        write_message(&mut client, &response).await.unwrap();
+        let received = tokio::time::timeout(
+            std::time::Duration::from_secs(1),
+            read_message::<Response, _>(&mut server),
+        )
+        .await
+        .expect("a message of exactly one chunk must not wait for more bytes")
+        .unwrap();
+        assert_eq!(received.payload, response.payload);
+    }
+
+    #[tokio::test]
+    async fn test_read_message_closed_early() {
+        let (mut client, mut server) = tokio::io::duplex(64);
+        client.write_all(b"{\"payload\":").await.unwrap();
+        drop(client);
+
+        let err = read_message::<Request, _>(&mut server).await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn test_is_incomplete() {
+        assert!(is_incomplete(b"{\"payload\":"));
+        assert!(!is_incomplete(b"{\"payload\":\"Ping\"}"));
+        assert!(!is_incomplete(b"not a request"));
+    }
+}
@@ -7,6 +7,12 @@ pub const SERVER_ADDRESS: &str = "127.0.0.1:3333";
 /// The maximum number of bytes that can be sent in a single chunk.
 pub const MAX_CHUNK_BYTE_SIZE: usize = 1024;
 
+/// The maximum number of bytes of a single message, a bigger message is rejected.
+pub const MAX_MESSAGE_BYTE_SIZE: usize = 16 * 1024 * 1024;
+
+/// The version of the request and response payloads, raised on incompatible changes.
+pub const SCHEMA_VERSION: u32 = 1;
+
 /// The log level for the logging framework.
 ///
 /// This constant represents the log level for the logging framework used in the program.
@@ -13,6 +13,10 @@ pub enum ConnectError {
     /// An IO error with the specified underlying error.
     #[error("IO error: {0}")]
     Io(#[from] io::Error),
+
+    /// The connection or the handshake did not complete in time.
+    #[error("Connection timed out")]
+    Timeout,
 }
 
 #[derive(Debug, Error)]
@@ -26,3 +30,13 @@ pub enum ProcessingErrorsResult {
     #[error("TypeMismatchError error: {0}")]
     TypeMismatchError(String),
 }
+
+/// Represents an error in a script line, see [`crate::script::parse_script`].
+#[derive(Debug, Error, PartialEq)]
+#[error("line {line}: {message}")]
+pub struct ParseError {
+    /// The line of the error, starting from 1.
+    pub line: usize,
+    /// The description of the error.
+    pub message: String,
+}
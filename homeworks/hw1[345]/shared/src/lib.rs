@@ -1,6 +1,10 @@
+pub mod codec;
 pub mod constants;
 pub mod errors;
+pub mod logging;
 pub mod models;
+pub mod protocol;
+pub mod script;
 
 pub use bank_engine::bank::Operation;
 pub use bank_engine::bank::OperationType;
@@ -0,0 +1,107 @@
+//! The logging setup shared by the server and the client examples.
+use crate::constants::LOG_LEVEL;
+use env_logger::{Env, Target};
+use log::debug;
+use std::fs::OpenOptions;
+use std::io;
+use std::path::PathBuf;
+
+/// The environment variable holding the path of the log file, the logs go to stderr without it.
+pub const LOG_FILE_ENV: &str = "BANK_LOG_FILE";
+
+/// Where the logs are written to.
+#[derive(Debug, Clone, PartialEq)]
+pub enum LogTarget {
+    Stderr,
+    Stdout,
+    /// The file the logs are appended to, it is created if missing.
+    File(PathBuf),
+}
+
+/// The settings of the logging, the `RUST_LOG` environment variable overrides the level.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogConfig {
+    /// The log level or filter, e.g. "debug" or "server=info".
+    pub level: String,
+    pub target: LogTarget,
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        Self {
+            level: LOG_LEVEL.to_string(),
+            target: LogTarget::Stderr,
+        }
+    }
+}
+
+impl LogConfig {
+    /// Creates the default config, writing to the file from `LOG_FILE_ENV` if it is set.
+    pub fn from_env() -> Self {
+        let target = std::env::var_os(LOG_FILE_ENV)
+            .map(|path| LogTarget::File(path.into()))
+            .unwrap_or(LogTarget::Stderr);
+        Self {
+            target,
+            ..Self::default()
+        }
+    }
+}
+
+/// Initializes the logger, the later calls keep the logger of the first one.
+///
+/// # Errors
+///
+/// Returns an error if the log file can not be opened.
+///
+pub fn init_logging(config: &LogConfig) -> io::Result<()> {
+    let mut builder =
+        env_logger::Builder::from_env(Env::default().default_filter_or(&config.level));
+    match &config.target {
+        LogTarget::Stderr => builder.target(Target::Stderr),
+        LogTarget::Stdout => builder.target(Target::Stdout),
+        LogTarget::File(path) => {
+            let file = OpenOptions::new().create(true).append(true).open(path)?;
+            builder.target(Target::Pipe(Box::new(file)))
+        }
+    };
+    if builder.try_init().is_err() {
+        debug!("The logger is already initialized");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_init_logging_is_idempotent() {
+        let file = std::env::temp_dir().join("bank_init_logging_test.log");
+        let configs = [
+            LogConfig::default(),
+            LogConfig {
+                level: "info".to_string(),
+                target: LogTarget::Stdout,
+            },
+            LogConfig {
+                level: "warn".to_string(),
+                target: LogTarget::File(file.clone()),
+            },
+        ];
+        for config in configs.iter().chain(configs.iter()) {
+            init_logging(config).unwrap();
+        }
+        assert!(file.exists());
+        let _ = std::fs::remove_file(file);
+    }
+
+    #[test]
+    fn test_init_logging_bad_file() {
+        let config = LogConfig {
+            level: LOG_LEVEL.to_string(),
+            target: LogTarget::File(std::env::temp_dir().join("missing-dir").join("bank.log")),
+        };
+        assert!(init_logging(&config).is_err());
+    }
+}
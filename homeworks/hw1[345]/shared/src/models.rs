@@ -1,8 +1,8 @@
-use crate::constants::MAX_CHUNK_BYTE_SIZE;
+use crate::codec;
 use crate::errors::ProcessingErrorsResult;
 use bank_engine::bank::{Operation, TransactionId};
 use serde::{Deserialize, Serialize};
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use task2::{PayloadDisplay, VariantNames};
 use tokio::net::TcpStream;
 
 #[derive(Serialize, Debug, Deserialize)]
@@ -12,13 +12,11 @@ pub struct Request {
 
 impl Request {
     pub async fn send(&self, stream: &mut TcpStream) -> Result<(), std::io::Error> {
-        let json = serde_json::to_vec(&self)?;
-        stream.write_all(&json).await?;
-        Ok(())
+        codec::write_message(stream, self).await
     }
 }
 
-#[derive(Serialize, Debug, Deserialize)]
+#[derive(Serialize, Debug, Deserialize, Clone, VariantNames)]
 pub enum RequestPayload {
     /// Represents a ping request.
     Ping,
@@ -46,10 +44,34 @@ pub enum RequestPayload {
 
     /// Represents a get history for account request with the specified account identifier.
     GetHistoryForAccount(String),
+
+    /// Represents a request to apply a batch of operations, allowed with the admin token only.
+    ImportOperations(ImportOperationsParams),
+
+    /// Represents a get operation request with the specified transaction identifier.
+    GetOperation(TransactionId),
+
+    /// Represents a request for the number of operations in the history.
+    GetHistoryLen,
+
+    /// Represents a request for the codes of the open accounts.
+    ListAccounts,
+
+    /// Represents the requests processed in order within a single round-trip.
+    Batch(Vec<RequestPayload>),
+
+    /// Represents a request to reverse the most recent operation made on the connection.
+    Undo,
+
+    /// Represents a request for the schema version and the requests the server handles.
+    Capabilities,
+
+    /// Represents a request to receive every new operation until the client disconnects.
+    Subscribe,
 }
 
 /// Represents the payload of a response.
-#[derive(Serialize, Debug, Deserialize, PartialEq)]
+#[derive(Serialize, Debug, Deserialize, PartialEq, PayloadDisplay)]
 pub enum ResponsePayload {
     /// Indicates that a handshake has been established.
     HandShakeEstablished,
@@ -78,51 +100,109 @@ pub enum ResponsePayload {
     /// Indicates an error occurred while making a transfer to same account
     SomeAccountError(String),
 
+    /// Indicates that the most recent operation was reversed by the specified transaction.
+    UndoSuccess(TransactionId),
+
     /// Represents the balance of an account with the specified amount.
     Balance(f64),
 
     /// Represents the history of operations for an account with the specified list of operations.
     History(Vec<Operation>),
-    /// Represents an error occurred while getting the history with the specified error message.
-    DeserializeError(String),
+    /// Indicates how many imported operations were applied and which were skipped.
+    OperationsImported {
+        applied: usize,
+        skipped: Vec<TransactionId>,
+    },
+
+    /// Represents the requested operation, `None` if it is not in the history.
+    Operation(Option<Operation>),
+
+    /// Represents the responses for the requests of a batch, in the same order.
+    Batch(Vec<ResponsePayload>),
+
+    /// Represents the number of operations in the history.
+    HistoryLen(usize),
+
+    /// Represents the codes of the open accounts sorted alphabetically.
+    AccountList(Vec<String>),
+
+    /// Indicates that the connection now receives the new operations as events.
+    Subscribed,
+
+    /// Represents a new operation pushed to a subscribed connection.
+    Event(Operation),
+
+    /// Represents the schema version and the names of the requests the server handles.
+    Capabilities {
+        version: u32,
+        supported_requests: Vec<String>,
+    },
+    /// Indicates that the request could not be deserialized, with the position of the error.
+    DeserializeError {
+        message: String,
+        /// The line of the error, starting from 1.
+        line: usize,
+        /// The column of the error, starting from 1.
+        column: usize,
+        /// The byte offset of the error in the request.
+        offset: usize,
+        /// The part of the request around the error.
+        snippet: String,
+    },
 }
 
 /// Represents the parameters for an open account request.
-#[derive(Serialize, Debug, Deserialize)]
+#[derive(Serialize, Debug, Deserialize, Clone)]
 pub struct OpenAccountRequestParams {
     /// The account identifier for the new account.
     pub account: String,
 }
 
 /// Represents the parameters for a get balance request.
-#[derive(Serialize, Debug, Deserialize)]
+#[derive(Serialize, Debug, Deserialize, Clone)]
 pub struct GetBalanceAccountRequestParams {
     /// The account identifier for which the balance is requested.
     pub account: String,
 }
 
 /// Represents the parameters for a deposit request.
-#[derive(Serialize, Debug, Deserialize)]
+#[derive(Serialize, Debug, Deserialize, Clone)]
 pub struct DepositParams {
     /// The account identifier where the deposit will be made.
     pub account: String,
 
     /// The amount to be deposited.
     pub amount: f64,
+
+    /// The optional category tag of the deposit, e.g. "salary".
+    #[serde(default)]
+    pub category: Option<String>,
+
+    /// The optional key making the retries of the deposit apply it only once.
+    #[serde(default)]
+    pub idempotency_key: Option<String>,
 }
 
 /// Represents the parameters for a withdrawal request.
-#[derive(Serialize, Debug, Deserialize)]
+#[derive(Serialize, Debug, Deserialize, Clone)]
 pub struct WithdrawParams {
     /// The account identifier from which the withdrawal will be made.
     pub account: String,
 
     /// The amount to be withdrawn.
     pub amount: f64,
+
+    /// The optional category tag of the withdrawal, e.g. "groceries".
+    #[serde(default)]
+    pub category: Option<String>,
+
+    /// The optional key making the retries of the withdrawal apply it only once.
+    #[serde(default)]
+    pub idempotency_key: Option<String>,
 }
 
 /// Represents the parameters for a transfer request.
-#[derive(Serialize, Debug, Deserialize)]
+#[derive(Serialize, Debug, Deserialize, Clone)]
 pub struct TransferParams {
     /// The account identifier of the sender.
     pub sender_account: String,
@@ -134,6 +214,16 @@ pub struct TransferParams {
     pub amount: f64,
 }
 
+/// Represents the parameters for an import operations request.
+#[derive(Serialize, Debug, Deserialize, Clone)]
+pub struct ImportOperationsParams {
+    /// The admin token configured on the server.
+    pub admin_token: String,
+
+    /// The operations to apply in order.
+    pub operations: Vec<Operation>,
+}
+
 /// Represents a response from the server.
 #[derive(Serialize, Debug, Deserialize)]
 pub struct Response {
@@ -146,22 +236,43 @@ pub type ResponseResult = Result<Response, ProcessingErrorsResult>;
 
 impl Response {
     pub async fn new(stream: &mut TcpStream) -> Result<Self, std::io::Error> {
-        let mut received: Vec<u8> = vec![];
-        let mut chunk = vec![0u8; MAX_CHUNK_BYTE_SIZE];
-        loop {
-            let bytes_read = stream.read(&mut chunk).await.unwrap();
-            received.extend_from_slice(&chunk[..bytes_read]);
-            if bytes_read < MAX_CHUNK_BYTE_SIZE {
-                break;
+        codec::read_message(stream).await
+    }
+
+    pub async fn send(&self, stream: &mut TcpStream) -> Result<(), std::io::Error> {
+        codec::write_message(stream, self).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_variant_names() {
+        assert!(RequestPayload::VARIANT_NAMES.contains(&"Ping"));
+        for name in RequestPayload::VARIANT_NAMES {
+            // the variants with fields fail on the missing content, not on the name
+            if let Err(err) = serde_json::from_str::<RequestPayload>(&format!("\"{name}\"")) {
+                assert!(!err.to_string().contains("unknown variant"), "{err}");
             }
         }
-        let resp = serde_json::from_slice::<Response>(received.as_slice())?;
-        Ok(resp)
     }
 
-    pub async fn send(&self, stream: &mut TcpStream) -> Result<(), std::io::Error> {
-        let json = serde_json::to_vec(&self)?;
-        stream.write_all(&json).await?;
-        Ok(())
+    #[test]
+    fn test_response_payload_display() {
+        assert_eq!(
+            ResponsePayload::HandShakeEstablished.to_string(),
+            "hand shake established"
+        );
+        assert_eq!(ResponsePayload::Balance(42.5).to_string(), "balance: 42.5");
+        assert_eq!(
+            ResponsePayload::DepositError("Account does not exist".to_string()).to_string(),
+            "deposit error: \"Account does not exist\""
+        );
+        assert_eq!(
+            ResponsePayload::History(vec![]).to_string(),
+            "history: 0 items"
+        );
     }
 }
@@ -0,0 +1,95 @@
+//! Negotiation of the encoding used for the messages, kept free of sockets so both sides can test it.
+use serde::{Deserialize, Serialize};
+
+/// An encoding of the messages on the wire.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WireFormat {
+    Json,
+    Bincode,
+}
+
+/// The encoding agreed on by the client and the server.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NegotiatedFormat {
+    pub format: WireFormat,
+    /// Set when none of the client preferences is supported by the server.
+    pub is_fallback: bool,
+}
+
+/// The encoding used when the client and the server have nothing in common, every peer speaks it.
+pub const FALLBACK_FORMAT: WireFormat = WireFormat::Json;
+
+/// Picks the first format of the client preferences that the server supports.
+///
+/// # Arguments
+///
+/// * `client_prefs` - The formats requested by the client, the most preferred first.
+/// * `server_caps` - The formats supported by the server.
+///
+/// # Returns
+///
+/// The agreed format, or `FALLBACK_FORMAT` if no preference is supported.
+///
+pub fn negotiate(client_prefs: &[WireFormat], server_caps: &[WireFormat]) -> NegotiatedFormat {
+    match client_prefs
+        .iter()
+        .find(|format| server_caps.contains(format))
+    {
+        Some(format) => NegotiatedFormat {
+            format: *format,
+            is_fallback: false,
+        },
+        None => NegotiatedFormat {
+            format: FALLBACK_FORMAT,
+            is_fallback: true,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_negotiate_bincode() {
+        let negotiated = negotiate(
+            &[WireFormat::Bincode, WireFormat::Json],
+            &[WireFormat::Json, WireFormat::Bincode],
+        );
+        assert_eq!(
+            negotiated,
+            NegotiatedFormat {
+                format: WireFormat::Bincode,
+                is_fallback: false,
+            }
+        );
+    }
+
+    #[test]
+    fn test_negotiate_json() {
+        let negotiated = negotiate(
+            &[WireFormat::Bincode, WireFormat::Json],
+            &[WireFormat::Json],
+        );
+        assert_eq!(
+            negotiated,
+            NegotiatedFormat {
+                format: WireFormat::Json,
+                is_fallback: false,
+            }
+        );
+    }
+
+    #[test]
+    fn test_negotiate_unsupported_falls_back() {
+        let negotiated = negotiate(&[WireFormat::Bincode], &[WireFormat::Json]);
+        assert_eq!(
+            negotiated,
+            NegotiatedFormat {
+                format: FALLBACK_FORMAT,
+                is_fallback: true,
+            }
+        );
+        assert!(negotiate(&[], &[WireFormat::Bincode]).is_fallback);
+    }
+}
@@ -0,0 +1,146 @@
+//! A simple script format for the demos, one request per line:
+//!
+//! ```text
+//! # comments and blank lines are skipped
+//! create Alice
+//! deposit Alice 100
+//! withdraw Alice 10
+//! transfer Alice Bob 25
+//! balance Alice
+//! ```
+use crate::errors::ParseError;
+use crate::models::{
+    DepositParams, GetBalanceAccountRequestParams, OpenAccountRequestParams, RequestPayload,
+    TransferParams, WithdrawParams,
+};
+
+/// Parses the script into the request payloads, in the order of the lines.
+///
+/// # Errors
+///
+/// Returns [`ParseError`] with the number of the first invalid line.
+pub fn parse_script(s: &str) -> Result<Vec<RequestPayload>, ParseError> {
+    s.lines()
+        .enumerate()
+        .filter(|(_, line)| {
+            let line = line.trim();
+            !line.is_empty() && !line.starts_with('#')
+        })
+        .map(|(ind, line)| {
+            parse_line(line).map_err(|message| ParseError {
+                line: ind + 1,
+                message,
+            })
+        })
+        .collect()
+}
+
+fn parse_line(line: &str) -> Result<RequestPayload, String> {
+    let words = line.split_whitespace().collect::<Vec<_>>();
+    let payload = match words.as_slice() {
+        ["create", account] => RequestPayload::OpenAccount(OpenAccountRequestParams {
+            account: account.to_string(),
+        }),
+        ["deposit", account, amount] => RequestPayload::Deposit(DepositParams {
+            account: account.to_string(),
+            amount: parse_amount(amount)?,
+            category: None,
+            idempotency_key: None,
+        }),
+        ["withdraw", account, amount] => RequestPayload::Withdraw(WithdrawParams {
+            account: account.to_string(),
+            amount: parse_amount(amount)?,
+            category: None,
+            idempotency_key: None,
+        }),
+        ["transfer", sender, receiver, amount] => RequestPayload::Transfer(TransferParams {
+            sender_account: sender.to_string(),
+            receiver_account: receiver.to_string(),
+            amount: parse_amount(amount)?,
+        }),
+        ["balance", account] => RequestPayload::GetBalance(GetBalanceAccountRequestParams {
+            account: account.to_string(),
+        }),
+        [command @ ("create" | "deposit" | "withdraw" | "transfer" | "balance"), ..] => {
+            return Err(format!("wrong number of arguments for `{command}`"))
+        }
+        [command, ..] => return Err(format!("unknown command `{command}`")),
+        [] => unreachable!("blank lines are skipped"),
+    };
+    Ok(payload)
+}
+
+fn parse_amount(amount: &str) -> Result<f64, String> {
+    match amount.parse::<f64>() {
+        Ok(value) if value.is_finite() => Ok(value),
+        _ => Err(format!("invalid amount `{amount}`")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_script() {
+        let script = "
+            # demo
+            create Alice
+            create Bob
+            deposit Alice 100
+            withdraw Alice 10.5
+            transfer Alice Bob 25
+            balance Bob
+        ";
+        let payloads = parse_script(script).unwrap();
+        assert_eq!(payloads.len(), 6);
+        assert!(matches!(
+            &payloads[0],
+            RequestPayload::OpenAccount(params) if params.account == "Alice"
+        ));
+        assert!(matches!(
+            &payloads[2],
+            RequestPayload::Deposit(params) if params.account == "Alice" && params.amount == 100.0
+        ));
+        assert!(matches!(
+            &payloads[3],
+            RequestPayload::Withdraw(params) if params.amount == 10.5
+        ));
+        assert!(matches!(
+            &payloads[4],
+            RequestPayload::Transfer(params)
+                if params.sender_account == "Alice"
+                    && params.receiver_account == "Bob"
+                    && params.amount == 25.0
+        ));
+        assert!(matches!(
+            &payloads[5],
+            RequestPayload::GetBalance(params) if params.account == "Bob"
+        ));
+    }
+
+    #[test]
+    fn test_parse_script_errors() {
+        let err = parse_script("create Alice\n\ndeposit Alice lots").unwrap_err();
+        assert_eq!(
+            err,
+            ParseError {
+                line: 3,
+                message: "invalid amount `lots`".to_string()
+            }
+        );
+        assert_eq!(err.to_string(), "line 3: invalid amount `lots`");
+
+        assert_eq!(
+            parse_script("transfer Alice 25").unwrap_err().message,
+            "wrong number of arguments for `transfer`"
+        );
+        assert_eq!(
+            parse_script("create Alice\nclose Alice").unwrap_err(),
+            ParseError {
+                line: 2,
+                message: "unknown command `close`".to_string()
+            }
+        );
+    }
+}
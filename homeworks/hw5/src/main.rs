@@ -13,6 +13,8 @@ fn main() {
 
     let mut pairs: Pair = PairOps::default_pair();
     println!("{:?}", pairs.pair_vector_sum((3, 4)));
+    println!("{}", pairs.pair_dot((1, 2)));
+    println!("{:?}", pairs.pair_partial_cmp((1, 2)));
 
     println!("hw5 done");
 }
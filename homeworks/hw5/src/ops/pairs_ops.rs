@@ -1,9 +1,13 @@
+use std::cmp::Ordering;
+
 pub(crate) type Pair = (i32, i32);
 
 pub trait PairOps {
     fn default_pair() -> Self;
     fn pair_vector_sum(&mut self, b: Pair) -> &Pair;
     fn pair_scalar_sum(self, b: Pair) -> i32;
+    fn pair_dot(self, b: Pair) -> i32;
+    fn pair_partial_cmp(self, b: Pair) -> Option<Ordering>;
 }
 
 impl PairOps for Pair {
@@ -20,6 +24,17 @@ impl PairOps for Pair {
     fn pair_scalar_sum(self, b: Pair) -> i32 {
         self.0 + self.1 + b.0 + b.1
     }
+
+    fn pair_dot(self, b: Pair) -> i32 {
+        self.0 * b.0 + self.1 * b.1
+    }
+
+    // `Pair` is a tuple alias, so the ordering by sum can't be a `PartialOrd` impl
+    fn pair_partial_cmp(self, b: Pair) -> Option<Ordering> {
+        let zero = Pair::default_pair();
+        self.pair_scalar_sum(zero)
+            .partial_cmp(&b.pair_scalar_sum(zero))
+    }
 }
 
 #[cfg(test)]
@@ -47,4 +62,18 @@ mod tests_pairs_ops {
         assert_eq!(pair.pair_scalar_sum((0, 0)), 0);
         assert_eq!(pair.pair_scalar_sum((-1, 1)), 0);
     }
+
+    #[test]
+    fn test_pair_dot() {
+        assert_eq!((1, 2).pair_dot((3, 4)), 11);
+        assert_eq!((0, 0).pair_dot((3, 4)), 0);
+        assert_eq!((-1, 2).pair_dot((3, 4)), 5);
+    }
+
+    #[test]
+    fn test_pair_partial_cmp() {
+        assert_eq!((1, 2).pair_partial_cmp((3, 4)), Some(Ordering::Less));
+        assert_eq!((5, 2).pair_partial_cmp((3, 4)), Some(Ordering::Equal));
+        assert_eq!((5, 5).pair_partial_cmp((3, 4)), Some(Ordering::Greater));
+    }
 }
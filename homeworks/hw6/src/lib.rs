@@ -1,3 +1,4 @@
+#[derive(Clone, Copy)]
 pub enum Item {
     First,
     Second,
@@ -5,6 +6,8 @@ pub enum Item {
 }
 
 impl Item {
+    pub const ALL: [Item; 3] = [Item::First, Item::Second, Item::Third];
+
     pub fn index(&self) -> usize {
         match self {
             Item::First => 0,
@@ -25,6 +28,28 @@ trait Container3Elements: Default + PartialEq<Self> {
     fn sum(&self) -> f64 {
         self.get_item(Item::First) + self.get_item(Item::Second) + self.get_item(Item::Third)
     }
+    fn product(&self) -> f64 {
+        self.get_item(Item::First) * self.get_item(Item::Second) * self.get_item(Item::Third)
+    }
+    fn average(&self) -> f64 {
+        self.sum() / 3.0
+    }
+    /// Multiplies every item by the factor, the value is stored the way `set_item` stores it,
+    /// e.g. the `u32` item of `Tuple` is truncated towards zero and a negative value becomes zero.
+    fn scale(&mut self, factor: f64) {
+        for item in Item::ALL {
+            self.set_item(item, self.get_item(item) * factor);
+        }
+    }
+    fn reset(&mut self) {
+        for item in Item::ALL {
+            self.set_item(item, 0.0);
+        }
+    }
+    /// Returns the values of the items in the `Item` order.
+    fn items(&self) -> [f64; 3] {
+        Item::ALL.map(|item| self.get_item(item))
+    }
     fn get_item(&self, item: Item) -> f64;
     fn set_item(&mut self, item: Item, value: f64);
 }
@@ -95,6 +120,23 @@ mod tests_container {
         check_container_sum(&tuple, 6.0);
     }
 
+    #[test]
+    fn test_container_product_and_average() {
+        let tuple = Tuple(2, 3.0, 4.0);
+        assert_eq!(tuple.product(), 24.0);
+        assert_eq!(tuple.average(), 3.0);
+        let arr = Array([1.5, 2.0, -4.0]);
+        assert_eq!(arr.product(), -12.0);
+        assert_eq!(arr.average(), -0.5 / 3.0);
+    }
+
+    #[test]
+    fn test_container_items() {
+        assert_eq!(Array([1.0, 2.0, 3.0]).items(), [1.0, 2.0, 3.0]);
+        assert_eq!(Tuple(1, 2.0, 3.0).items(), [1.0, 2.0, 3.0]);
+        assert_eq!(Tuple::default().items(), [0.0; 3]);
+    }
+
     #[test]
     fn test_container_default_values() {
         check_container_default_values(&Tuple::default());
@@ -106,6 +148,15 @@ mod tests_container {
 mod tests_array {
     use super::*;
 
+    #[test]
+    fn test_array_scale_and_reset() {
+        let mut arr = Array([1.0, -2.5, 3.0]);
+        arr.scale(2.0);
+        assert_eq!(arr.0, [2.0, -5.0, 6.0]);
+        arr.reset();
+        assert!(arr.is_default());
+    }
+
     #[test]
     fn test_array_default_values() {
         let arr = Array::default();
@@ -182,6 +233,27 @@ mod tests_tuple {
         assert_eq!(tup.get_item(Item::First), 0.0);
     }
 
+    #[test]
+    fn test_tuple_scale_truncates_first_item() {
+        let mut tup = Tuple(3, 1.5, 2.0);
+        tup.scale(1.5);
+        assert_eq!(tup.0, 4);
+        assert_eq!(tup.1, 2.25);
+        assert_eq!(tup.2, 3.0);
+
+        let mut tup = Tuple(3, 1.0, 1.0);
+        tup.scale(-1.0);
+        assert_eq!(tup.0, 0);
+        assert_eq!(tup.1, -1.0);
+    }
+
+    #[test]
+    fn test_tuple_reset() {
+        let mut tup = Tuple(1, 2.0, 3.0);
+        tup.reset();
+        assert!(tup.is_default());
+    }
+
     #[test]
     fn test_tuple_sum() {
         let tup = Tuple(1, 2.0, 3.0);
@@ -1,2 +1,5 @@
+extern crate self as hw8;
+
+pub mod summable;
 mod task1;
 mod task2;
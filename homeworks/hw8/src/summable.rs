@@ -0,0 +1,47 @@
+/// Derive macro generating [`Summable`] for structs with numeric fields only.
+pub use task2::Summable;
+
+/// Sums the numeric fields of a struct.
+pub trait Summable {
+    /// Returns the sum of all fields converted to `f64`.
+    fn sum_fields(&self) -> f64;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Summable;
+
+    #[derive(Summable)]
+    struct Point {
+        x: f64,
+        y: f32,
+        z: i32,
+    }
+
+    #[derive(Summable)]
+    struct Sample(u8, i16, u32, i64, usize);
+
+    #[derive(Summable)]
+    struct Unit;
+
+    #[test]
+    fn test_sum_named_fields() {
+        let point = Point {
+            x: 1.5,
+            y: 2.5,
+            z: -3,
+        };
+        assert_eq!(point.sum_fields(), 1.0);
+    }
+
+    #[test]
+    fn test_sum_tuple_fields() {
+        let sample = Sample(1, -2, 3, -4, 5);
+        assert_eq!(sample.sum_fields(), 3.0);
+    }
+
+    #[test]
+    fn test_sum_no_fields() {
+        assert_eq!(Unit.sum_fields(), 0.0);
+    }
+}
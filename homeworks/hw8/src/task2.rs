@@ -9,6 +9,9 @@ mod tests {
     gen_dummy_function!(3);
     gen_dummy_function!(4);
     gen_dummy_function!(9);
+    gen_dummy_function!(name = "compute", len = 3);
+    gen_dummy_function!(value = 7, name = "seven");
+    gen_dummy_function!(len = 5);
 
     #[test]
     fn test_2() {
@@ -18,4 +21,18 @@ mod tests {
         assert_eq!(fooo_result, 4);
         assert_eq!(foooooooooo_result, 10);
     }
+
+    #[test]
+    fn test_odd_len_names_are_skipped() {
+        // `missing` is never defined, the odd length name must not be called.
+        let (fo_result,) = even_len_name_func_invoke!("fo", "missing");
+        assert_eq!(fo_result, 2);
+    }
+
+    #[test]
+    fn test_gen_dummy_function_with_args() {
+        assert_eq!(compute(), 4);
+        assert_eq!(seven(), 7);
+        assert_eq!(fooooo(), 6);
+    }
 }
@@ -1,23 +1,324 @@
 use proc_macro::TokenStream;
 use proc_macro2::{Ident, Span};
+use syn::ext::IdentExt;
 
+/// Calls the functions whose names have an even length and returns their results as a tuple.
+///
+/// The names are string literals, the odd length ones are skipped:
+///
+/// ```
+/// fn ab() -> u32 { 2 }
+/// assert_eq!(task2::even_len_name_func_invoke!("ab", "missing"), (2,));
+/// ```
+///
+/// The length of a raw identifier does not count its `r#` prefix:
+///
+/// ```
+/// fn ab() -> u32 { 2 }
+/// assert_eq!(task2::even_len_name_func_invoke!("r#ab", "r#abc"), (2,));
+/// ```
+///
+/// A name that is not a valid identifier is reported as a compile error:
+///
+/// ```compile_fail
+/// fn ab() -> u32 { 2 }
+/// task2::even_len_name_func_invoke!("ab", "no way");
+/// ```
 #[proc_macro]
 pub fn even_len_name_func_invoke(input: TokenStream) -> TokenStream {
-    let even_func_name_idents = input
-        .into_iter()
-        .map(|func_name| func_name.to_string().replace('\"', ""))
-        .filter(|func_name| func_name.to_string().len() % 2 == 0)
-        .map(|func_name| Ident::new(&func_name, Span::call_site()))
-        .collect::<Vec<_>>();
+    let func_names = syn::parse_macro_input!(
+        input with syn::punctuated::Punctuated::<syn::LitStr, syn::Token![,]>::parse_terminated
+    );
+    let mut even_func_name_idents = Vec::new();
+    for func_name in func_names {
+        let name = func_name.value();
+        let Ok(mut ident) = syn::parse_str::<Ident>(&name) else {
+            return syn::Error::new_spanned(
+                &func_name,
+                format!("`{name}` is not a valid function name"),
+            )
+            .to_compile_error()
+            .into();
+        };
+        if ident.unraw().to_string().len() % 2 == 0 {
+            ident.set_span(func_name.span());
+            even_func_name_idents.push(ident);
+        }
+    }
     TokenStream::from(quote::quote! {
     (  #(#even_func_name_idents() , )* )
     })
 }
 
+/// Generates a dummy function returning a constant.
+///
+/// The bare form `gen_dummy_function!(3)` generates `fooo() -> u32 { 4 }`, the name repeats `o`
+/// `len` times and the function returns `len + 1`. The keyed form accepts `name`, `len` and `value`
+/// in any order, missing keys fall back to the bare form defaults:
+///
+/// ```
+/// task2::gen_dummy_function!(name = "compute", len = 3);
+/// task2::gen_dummy_function!(name = "answer", value = 42);
+/// assert_eq!(compute(), 4);
+/// assert_eq!(answer(), 42);
+/// ```
+///
+/// Malformed input is reported as a compile error:
+///
+/// ```compile_fail
+/// task2::gen_dummy_function!(name = 3);
+/// ```
+///
+/// ```compile_fail
+/// task2::gen_dummy_function!(size = 3);
+/// ```
+///
+/// ```compile_fail
+/// task2::gen_dummy_function!(name = "not an ident");
+/// ```
 #[proc_macro]
 pub fn gen_dummy_function(item: TokenStream) -> TokenStream {
-    let length = item.to_string().parse().unwrap();
-    let func_name = "f".to_string() + &*"o".to_string().repeat(length);
-    let func_src = format!("fn {func_name}() -> u32 {{ {} }}", length + 1);
-    func_src.parse().unwrap()
+    let args = syn::parse_macro_input!(item as DummyFunctionArgs);
+    let len = args.len.unwrap_or(0);
+    let func_name = args
+        .name
+        .unwrap_or_else(|| Ident::new(&format!("f{}", "o".repeat(len)), Span::call_site()));
+    let Some(value) = args.value.or_else(|| {
+        len.checked_add(1)
+            .and_then(|value| u32::try_from(value).ok())
+    }) else {
+        return syn::Error::new(Span::call_site(), "len is too large for a u32 result")
+            .to_compile_error()
+            .into();
+    };
+    TokenStream::from(quote::quote! {
+        fn #func_name() -> u32 {
+            #value
+        }
+    })
+}
+
+/// Input of [`gen_dummy_function`], either a bare `len` or `key = literal` pairs.
+#[derive(Default)]
+struct DummyFunctionArgs {
+    name: Option<Ident>,
+    len: Option<usize>,
+    value: Option<u32>,
+}
+
+impl syn::parse::Parse for DummyFunctionArgs {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let mut args = DummyFunctionArgs::default();
+        if input.peek(syn::LitInt) {
+            args.len = Some(input.parse::<syn::LitInt>()?.base10_parse()?);
+            return Ok(args);
+        }
+
+        let pairs =
+            syn::punctuated::Punctuated::<syn::MetaNameValue, syn::Token![,]>::parse_terminated(
+                input,
+            )?;
+        for pair in pairs {
+            let key = pair.path.require_ident()?;
+            let syn::Expr::Lit(syn::ExprLit { lit, .. }) = &pair.value else {
+                return Err(syn::Error::new_spanned(&pair.value, "expected a literal"));
+            };
+            match (key.to_string().as_str(), lit) {
+                ("name", syn::Lit::Str(name)) => args.name = Some(name.parse()?),
+                ("len", syn::Lit::Int(len)) => args.len = Some(len.base10_parse()?),
+                ("value", syn::Lit::Int(value)) => args.value = Some(value.base10_parse()?),
+                ("name", _) => return Err(syn::Error::new_spanned(lit, "name must be a string")),
+                ("len" | "value", _) => {
+                    return Err(syn::Error::new_spanned(
+                        lit,
+                        format!("{key} must be an integer"),
+                    ))
+                }
+                _ => {
+                    return Err(syn::Error::new_spanned(
+                        key,
+                        "unknown key, expected `name`, `len` or `value`",
+                    ))
+                }
+            }
+        }
+        Ok(args)
+    }
+}
+
+#[proc_macro_derive(PayloadDisplay)]
+pub fn payload_display_derive(input: TokenStream) -> TokenStream {
+    let input = syn::parse_macro_input!(input as syn::DeriveInput);
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let syn::Data::Enum(data) = &input.data else {
+        return syn::Error::new_spanned(name, "PayloadDisplay can only be derived for enums")
+            .to_compile_error()
+            .into();
+    };
+
+    let arms = data.variants.iter().map(|variant| {
+        let ident = &variant.ident;
+        let title = variant_title(&ident.to_string());
+        match &variant.fields {
+            syn::Fields::Unit => quote::quote! {
+                #name::#ident => write!(f, "{}", #title)
+            },
+            syn::Fields::Unnamed(fields) if fields.unnamed.is_empty() => quote::quote! {
+                #name::#ident() => write!(f, "{}", #title)
+            },
+            syn::Fields::Unnamed(fields) => {
+                let bindings = (0..fields.unnamed.len())
+                    .map(|ind| Ident::new(&format!("field_{ind}"), Span::call_site()))
+                    .collect::<Vec<_>>();
+                let values = fields
+                    .unnamed
+                    .iter()
+                    .zip(&bindings)
+                    .map(|(field, binding)| field_value(&field.ty, binding));
+                quote::quote! {
+                    #name::#ident(#(#bindings),*) => {
+                        write!(f, "{}: {}", #title, [#(#values),*].join(", "))
+                    }
+                }
+            }
+            syn::Fields::Named(fields) => {
+                let names = fields
+                    .named
+                    .iter()
+                    .filter_map(|field| field.ident.clone())
+                    .collect::<Vec<_>>();
+                let bindings = (0..names.len())
+                    .map(|ind| Ident::new(&format!("field_{ind}"), Span::call_site()))
+                    .collect::<Vec<_>>();
+                let values = fields.named.iter().zip(names.iter().zip(&bindings)).map(
+                    |(field, (name, binding))| {
+                        let label = name.to_string();
+                        let value = field_value(&field.ty, binding);
+                        quote::quote! { format!("{}={}", #label, #value) }
+                    },
+                );
+                quote::quote! {
+                    #name::#ident { #(#names: #bindings),* } => {
+                        write!(f, "{}: {}", #title, [#(#values),*].join(", "))
+                    }
+                }
+            }
+        }
+    });
+
+    TokenStream::from(quote::quote! {
+        impl #impl_generics std::fmt::Display for #name #ty_generics #where_clause {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                match self {
+                    #(#arms,)*
+                }
+            }
+        }
+    })
+}
+
+/// Derives the `VARIANT_NAMES` constant of an enum, the names of its variants in declaration order.
+///
+/// ```
+/// #[derive(task2::VariantNames)]
+/// enum Request {
+///     Ping,
+///     Deposit(u32),
+///     Transfer { amount: u32 },
+/// }
+/// assert_eq!(Request::VARIANT_NAMES, ["Ping", "Deposit", "Transfer"]);
+/// ```
+#[proc_macro_derive(VariantNames)]
+pub fn variant_names_derive(input: TokenStream) -> TokenStream {
+    let input = syn::parse_macro_input!(input as syn::DeriveInput);
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let syn::Data::Enum(data) = &input.data else {
+        return syn::Error::new_spanned(name, "VariantNames can only be derived for enums")
+            .to_compile_error()
+            .into();
+    };
+
+    let names = data
+        .variants
+        .iter()
+        .map(|variant| variant.ident.unraw().to_string());
+    TokenStream::from(quote::quote! {
+        impl #impl_generics #name #ty_generics #where_clause {
+            /// The names of the variants in declaration order.
+            pub const VARIANT_NAMES: &'static [&'static str] = &[#(#names),*];
+        }
+    })
+}
+
+/// Derives `hw8::summable::Summable`, summing all fields of a struct converted to `f64`.
+///
+/// Only the primitive numeric field types are accepted, any other type is a compile error.
+#[proc_macro_derive(Summable)]
+pub fn summable_derive(input: TokenStream) -> TokenStream {
+    let input = syn::parse_macro_input!(input as syn::DeriveInput);
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let syn::Data::Struct(data) = &input.data else {
+        return syn::Error::new_spanned(name, "Summable can only be derived for structs")
+            .to_compile_error()
+            .into();
+    };
+
+    let mut values = Vec::new();
+    for (ind, field) in data.fields.iter().enumerate() {
+        if !is_numeric(&field.ty) {
+            return syn::Error::new_spanned(&field.ty, "Summable fields must be numeric")
+                .to_compile_error()
+                .into();
+        }
+        let member = match &field.ident {
+            Some(ident) => syn::Member::Named(ident.clone()),
+            None => syn::Member::Unnamed(ind.into()),
+        };
+        values.push(quote::quote! { self.#member as f64 });
+    }
+
+    TokenStream::from(quote::quote! {
+        impl #impl_generics ::hw8::summable::Summable for #name #ty_generics #where_clause {
+            fn sum_fields(&self) -> f64 {
+                0.0 #(+ #values)*
+            }
+        }
+    })
+}
+
+/// Checks whether the type is one of the primitive numeric types.
+fn is_numeric(ty: &syn::Type) -> bool {
+    const NUMERIC_TYPES: [&str; 14] = [
+        "i8", "i16", "i32", "i64", "i128", "isize", "u8", "u16", "u32", "u64", "u128", "usize",
+        "f32", "f64",
+    ];
+    matches!(ty, syn::Type::Path(path)
+        if path.qself.is_none()
+            && path.path.get_ident().is_some_and(|ident| NUMERIC_TYPES.contains(&ident.to_string().as_str())))
+}
+
+/// Turns a `CamelCase` variant name into lowercase words, e.g. `DepositSuccess` => `deposit success`.
+fn variant_title(variant: &str) -> String {
+    let mut title = String::new();
+    for (ind, ch) in variant.chars().enumerate() {
+        if ch.is_uppercase() && ind != 0 {
+            title.push(' ');
+        }
+        title.extend(ch.to_lowercase());
+    }
+    title
+}
+
+/// Renders a variant field, collections are shortened to their length to keep the output concise.
+fn field_value(ty: &syn::Type, binding: &Ident) -> proc_macro2::TokenStream {
+    let is_vec = matches!(ty, syn::Type::Path(path)
+        if path.path.segments.last().is_some_and(|segment| segment.ident == "Vec"));
+    if is_vec {
+        quote::quote! { format!("{} items", #binding.len()) }
+    } else {
+        quote::quote! { format!("{:?}", #binding) }
+    }
 }
@@ -1,4 +1,48 @@
-use std::ops::{Add, Mul};
+use std::ops::{Add, Index, Mul, Sub};
+
+#[derive(Debug, PartialEq)]
+pub struct OverflowError;
+
+pub trait CheckedAdd: Sized {
+    fn checked_add(self, rhs: Self) -> Option<Self>;
+}
+
+macro_rules! impl_checked_add {
+    ($($t:ty),*) => {
+        $(
+            impl CheckedAdd for $t {
+                fn checked_add(self, rhs: Self) -> Option<Self> {
+                    <$t>::checked_add(self, rhs)
+                }
+            }
+        )*
+    };
+}
+
+impl_checked_add!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+
+pub trait IntegerMul: Sized {
+    fn saturating_mul(self, rhs: Self) -> Self;
+    fn wrapping_mul(self, rhs: Self) -> Self;
+}
+
+macro_rules! impl_integer_mul {
+    ($($t:ty),*) => {
+        $(
+            impl IntegerMul for $t {
+                fn saturating_mul(self, rhs: Self) -> Self {
+                    <$t>::saturating_mul(self, rhs)
+                }
+
+                fn wrapping_mul(self, rhs: Self) -> Self {
+                    <$t>::wrapping_mul(self, rhs)
+                }
+            }
+        )*
+    };
+}
+
+impl_integer_mul!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
 
 pub struct Matrix<T, const N: usize> {
     elements: [T; N],
@@ -10,12 +54,86 @@ impl<T: Copy, const N: usize> Matrix<T, N> {
     }
 }
 
+impl<T, const N: usize> Matrix<T, N> {
+    pub fn as_slice(&self) -> &[T] {
+        &self.elements
+    }
+
+    pub fn get(&self, index: usize) -> Option<&T> {
+        self.elements.get(index)
+    }
+
+    pub fn set(&mut self, index: usize, value: T) -> Result<(), String> {
+        let element = self
+            .elements
+            .get_mut(index)
+            .ok_or_else(|| format!("index {index} is out of bounds for {N} elements"))?;
+        *element = value;
+        Ok(())
+    }
+}
+
+impl<T, const N: usize> Index<usize> for Matrix<T, N> {
+    type Output = T;
+
+    fn index(&self, index: usize) -> &T {
+        &self.elements[index]
+    }
+}
+
+impl<T: Clone, const N: usize> Matrix<T, N> {
+    pub fn to_vec(&self) -> Vec<T> {
+        self.elements.to_vec()
+    }
+}
+
 impl<T: Add<Output = T> + Copy, const N: usize> Matrix<T, N> {
     pub fn add(&mut self, value: T) {
         for i in 0..N {
             self.elements[i] = self.elements[i] + value;
         }
     }
+
+    /// Adds the corresponding elements of the other matrix, the sizes always match.
+    ///
+    /// ```compile_fail
+    /// let mut matrix = hw9::Matrix::<i32, 3>::new([1, 2, 3]);
+    /// matrix.add_matrix(&hw9::Matrix::<i32, 2>::new([4, 5]));
+    /// ```
+    pub fn add_matrix(&mut self, other: &Matrix<T, N>) {
+        for i in 0..N {
+            self.elements[i] = self.elements[i] + other.elements[i];
+        }
+    }
+}
+
+impl<T: Sub<Output = T> + Copy, const N: usize> Matrix<T, N> {
+    pub fn subtract(&mut self, value: T) {
+        for i in 0..N {
+            self.elements[i] = self.elements[i] - value;
+        }
+    }
+}
+
+impl<T: Mul<Output = T> + Add<Output = T> + Copy + Default, const N: usize> Matrix<T, N> {
+    /// Returns the sum of the products of the corresponding elements, `T::default()` if empty.
+    pub fn dot(&self, other: &Matrix<T, N>) -> T {
+        self.elements
+            .iter()
+            .zip(other.elements.iter())
+            .fold(T::default(), |sum, (left, right)| sum + *left * *right)
+    }
+}
+
+impl<T: CheckedAdd + Copy, const N: usize> Matrix<T, N> {
+    pub fn checked_add(&mut self, value: T) -> Result<(), OverflowError> {
+        let mut elements = self.elements;
+        for element in elements.iter_mut() {
+            *element = element.checked_add(value).ok_or(OverflowError)?;
+        }
+        self.elements = elements;
+        Ok(())
+    }
 }
 
 impl<T: Mul<Output = T> + Copy, const N: usize> Matrix<T, N> {
@@ -26,6 +144,20 @@ impl<T: Mul<Output = T> + Copy, const N: usize> Matrix<T, N> {
     }
 }
 
+impl<T: IntegerMul + Copy, const N: usize> Matrix<T, N> {
+    pub fn saturating_multiply(&mut self, value: T) {
+        for element in self.elements.iter_mut() {
+            *element = element.saturating_mul(value);
+        }
+    }
+
+    pub fn wrapping_multiply(&mut self, value: T) {
+        for element in self.elements.iter_mut() {
+            *element = element.wrapping_mul(value);
+        }
+    }
+}
+
 pub struct MatrixSet<'a, T, const N: usize> {
     matrices: &'a [Matrix<T, N>],
 }
@@ -40,6 +172,15 @@ impl<'a, T, const N: usize> MatrixSet<'a, T, N> {
     }
 }
 
+impl<'a, T, const N: usize> MatrixSet<'a, T, N> {
+    pub fn fold_all<B>(&self, init: B, f: impl FnMut(B, &T) -> B) -> B {
+        self.matrices
+            .iter()
+            .flat_map(|matrix| matrix.elements.iter())
+            .fold(init, f)
+    }
+}
+
 impl<'a, T: Add<Output = T> + Copy + Default, const N: usize> MatrixSet<'a, T, N> {
     pub fn sum_all_elements(&self) -> T {
         let mut sum: T = T::default();
@@ -52,23 +193,92 @@ impl<'a, T: Add<Output = T> + Copy + Default, const N: usize> MatrixSet<'a, T, N
     }
 }
 
-impl<'a, T: Mul<Output = T> + Copy + std::ops::Div<Output = T>, const N: usize>
-    MatrixSet<'a, T, N>
-{
-    pub fn multiply_all_elements(&self) -> T {
-        let mut product = self.matrices[0].elements[0];
-        for matrix in self.matrices {
-            for element in matrix.elements.iter() {
-                product = product * *element;
-            }
+impl<'a, T: Into<f64> + Copy, const N: usize> MatrixSet<'a, T, N> {
+    /// Returns the sum of all elements divided by their count, `0.0` if the set has no elements.
+    ///
+    /// The elements are summed as `f64`, so the sum does not overflow `T`.
+    pub fn average_all_elements(&self) -> f64 {
+        let count = self.matrices.len() * N;
+        if count == 0 {
+            return 0.0;
         }
-        product / self.matrices[0].elements[0]
+        self.fold_all(0.0, |sum, element| sum + (*element).into()) / count as f64
+    }
+}
+
+impl<'a, T: PartialOrd, const N: usize> MatrixSet<'a, T, N> {
+    pub fn min_element(&self) -> Option<&'a T> {
+        self.find_element(|element, min| element < min)
+    }
+
+    pub fn max_element(&self) -> Option<&'a T> {
+        self.find_element(|element, max| element > max)
+    }
+
+    /// Scans the elements in order, keeping the found one unless the next one replaces it.
+    fn find_element(&self, replaces: impl Fn(&T, &T) -> bool) -> Option<&'a T> {
+        self.matrices
+            .iter()
+            .flat_map(|matrix| matrix.elements.iter())
+            .reduce(|found, element| {
+                if replaces(element, found) {
+                    element
+                } else {
+                    found
+                }
+            })
+    }
+}
+
+impl<'a, T: Mul<Output = T> + Copy, const N: usize> MatrixSet<'a, T, N> {
+    /// Returns the product of all elements, `None` if the set has no elements.
+    pub fn multiply_all_elements(&self) -> Option<T> {
+        let mut elements = self
+            .matrices
+            .iter()
+            .flat_map(|matrix| matrix.elements.iter());
+        let first = *elements.next()?;
+        Some(elements.fold(first, |product, element| product * *element))
+    }
+}
+
+/// A matrix of `R` rows with `C` columns each.
+pub struct Matrix2D<T, const R: usize, const C: usize> {
+    rows: [[T; C]; R],
+}
+
+impl<T: Copy, const R: usize, const C: usize> Matrix2D<T, R, C> {
+    pub fn new(rows: [[T; C]; R]) -> Self {
+        Self { rows }
+    }
+
+    pub fn to_vec(&self) -> Vec<Vec<T>> {
+        self.rows.iter().map(|row| row.to_vec()).collect()
+    }
+
+    /// Puts the rows of the other matrix below the rows of this one.
+    ///
+    /// The size of the result can not be computed from `R` and `R2` on stable Rust,
+    /// so the rows are returned as vectors.
+    pub fn vstack<const R2: usize>(&self, other: &Matrix2D<T, R2, C>) -> Vec<Vec<T>> {
+        let mut rows = self.to_vec();
+        rows.extend(other.to_vec());
+        rows
+    }
+
+    /// Puts the columns of the other matrix right of the columns of this one.
+    pub fn hstack<const C2: usize>(&self, other: &Matrix2D<T, R, C2>) -> Vec<Vec<T>> {
+        self.rows
+            .iter()
+            .zip(other.rows.iter())
+            .map(|(row, other_row)| row.iter().chain(other_row.iter()).copied().collect())
+            .collect()
     }
 }
 
 #[cfg(test)]
 mod unit_tests_matrix {
-    use super::Matrix;
+    use super::{Matrix, OverflowError};
 
     #[test]
     fn test_matrix_new() {
@@ -76,12 +286,84 @@ mod unit_tests_matrix {
         assert_eq!(matrix.elements, [1, 2, 3]);
     }
 
+    #[test]
+    fn test_matrix_as_slice_and_to_vec() {
+        let matrix = Matrix::<i32, 3>::new([1, 2, 3]);
+        assert_eq!(matrix.as_slice(), &[1, 2, 3]);
+        assert_eq!(matrix.to_vec(), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_matrix_get_and_set() {
+        let mut matrix = Matrix::<i32, 3>::new([1, 2, 3]);
+        assert_eq!(matrix.get(2), Some(&3));
+        assert_eq!(matrix.get(3), None);
+
+        assert_eq!(matrix.set(0, 10), Ok(()));
+        assert_eq!(matrix[0], 10);
+        assert!(matrix.set(3, 4).is_err());
+        assert_eq!(matrix.elements, [10, 2, 3]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_matrix_index_out_of_bounds() {
+        let matrix = Matrix::<i32, 3>::new([1, 2, 3]);
+        let _ = matrix[3];
+    }
+
     #[test]
     fn test_matrix_add() {
         let mut matrix = Matrix::<u32, 3>::new([1, 2, 3]);
         matrix.add(10);
         assert_eq!(matrix.elements, [11, 12, 13]);
     }
+
+    #[test]
+    fn test_matrix_add_matrix() {
+        let mut matrix = Matrix::<i32, 3>::new([1, 2, 3]);
+        matrix.add_matrix(&Matrix::new([4, 5, 6]));
+        assert_eq!(matrix.elements, [5, 7, 9]);
+    }
+
+    #[test]
+    fn test_matrix_dot() {
+        let matrix = Matrix::<i32, 3>::new([1, 2, 3]);
+        let product: i32 = matrix.dot(&Matrix::new([4, 5, 6]));
+        assert_eq!(product, 32);
+
+        let matrix = Matrix::<f64, 2>::new([1.5, -2.0]);
+        let product: f64 = matrix.dot(&Matrix::new([2.0, 0.25]));
+        assert_eq!(product, 2.5);
+
+        let empty = Matrix::<u8, 0>::new([]);
+        assert_eq!(empty.dot(&Matrix::new([])), 0);
+    }
+
+    #[test]
+    fn test_matrix_subtract() {
+        let mut matrix = Matrix::<i32, 3>::new([1, 20, 30]);
+        matrix.subtract(10);
+        assert_eq!(matrix.elements, [-9, 10, 20]);
+
+        let mut matrix = Matrix::<f64, 3>::new([1.5, 2.0, -3.0]);
+        matrix.subtract(0.5);
+        assert_eq!(matrix.elements, [1.0, 1.5, -3.5]);
+    }
+
+    #[test]
+    fn test_matrix_checked_add() {
+        let mut matrix = Matrix::<i32, 3>::new([1, 2, 3]);
+        assert_eq!(matrix.checked_add(10), Ok(()));
+        assert_eq!(matrix.elements, [11, 12, 13]);
+    }
+
+    #[test]
+    fn test_matrix_checked_add_overflow() {
+        let mut matrix = Matrix::<i32, 3>::new([1, i32::MAX - 1, 3]);
+        assert_eq!(matrix.checked_add(2), Err(OverflowError));
+        assert_eq!(matrix.elements, [1, i32::MAX - 1, 3]);
+    }
     #[test]
     fn test_matrix_bound_add_and_multiply() {
         let matrix = Matrix::<char, 3>::new(['a', 'b', 'c']);
@@ -95,6 +377,20 @@ mod unit_tests_matrix {
         matrix1.multiply(2);
         assert_eq!(matrix1.elements, [2, 4, 6, 8]);
     }
+
+    #[test]
+    fn test_matrix_saturating_multiply() {
+        let mut matrix = Matrix::<i32, 3>::new([2, i32::MAX - 1, i32::MIN + 1]);
+        matrix.saturating_multiply(2);
+        assert_eq!(matrix.elements, [4, i32::MAX, i32::MIN]);
+    }
+
+    #[test]
+    fn test_matrix_wrapping_multiply() {
+        let mut matrix = Matrix::<i32, 3>::new([2, i32::MAX - 1, i32::MIN + 1]);
+        matrix.wrapping_multiply(2);
+        assert_eq!(matrix.elements, [4, -4, 2]);
+    }
 }
 #[cfg(test)]
 mod unit_tests_matrix_set {
@@ -119,12 +415,107 @@ mod unit_tests_matrix_set {
         assert_eq!(matrix_set.sum_all_elements(), 1 + 2 + 3 + 4 + 5 + 6);
     }
 
+    #[test]
+    fn test_matrix_set_average_all_elements() {
+        let binding = [
+            Matrix::<i32, 3>::new([1, 2, 3]),
+            Matrix::<i32, 3>::new([4, 5, 7]),
+        ];
+        let matrix_set = MatrixSet::new(&binding);
+        assert_eq!(matrix_set.average_all_elements(), 22.0 / 6.0);
+
+        let binding = [Matrix::<u8, 2>::new([200, 250]), Matrix::new([100, 50])];
+        assert_eq!(MatrixSet::new(&binding).average_all_elements(), 150.0);
+
+        let binding: [Matrix<i32, 3>; 0] = [];
+        assert_eq!(MatrixSet::new(&binding).average_all_elements(), 0.0);
+    }
+
+    #[test]
+    fn test_matrix_set_min_and_max_element() {
+        let binding = [
+            Matrix::<f64, 3>::new([1.5, -2.0, 3.0]),
+            Matrix::<f64, 3>::new([4.0, 7.25, 0.0]),
+        ];
+        let matrix_set = MatrixSet::new(&binding);
+        assert_eq!(matrix_set.min_element(), Some(&-2.0));
+        assert_eq!(matrix_set.max_element(), Some(&7.25));
+
+        let binding: [Matrix<f64, 3>; 0] = [];
+        let matrix_set = MatrixSet::new(&binding);
+        assert_eq!(matrix_set.min_element(), None);
+        assert_eq!(matrix_set.max_element(), None);
+    }
+
+    #[test]
+    fn test_matrix_set_fold_all() {
+        let matrix1 = Matrix::<i32, 3>::new([1, 7, 3]);
+        let matrix2 = Matrix::<i32, 3>::new([4, 5, 6]);
+        let binding = [matrix1, matrix2];
+        let matrix_set = MatrixSet::new(&binding);
+        assert_eq!(
+            matrix_set.fold_all(0, |count, element| count + (*element > 3) as usize),
+            4
+        );
+        assert_eq!(
+            matrix_set.fold_all(i32::MIN, |max, element| max.max(*element)),
+            7
+        );
+    }
+
     #[test]
     fn test_matrix_set_multiply_all_elements() {
         let matrix1 = Matrix::<i32, 3>::new([2, 1, 3]);
         let matrix2 = Matrix::<i32, 3>::new([4, 5, 6]);
         let binding = [matrix1, matrix2];
         let matrix_set = MatrixSet::new(&binding);
-        assert_eq!(matrix_set.multiply_all_elements(), 1 * 2 * 3 * 4 * 5 * 6);
+        assert_eq!(
+            matrix_set.multiply_all_elements(),
+            Some(1 * 2 * 3 * 4 * 5 * 6)
+        );
+    }
+
+    #[test]
+    fn test_matrix_set_multiply_all_elements_with_zero_first() {
+        let binding = [Matrix::<i32, 2>::new([0, 5])];
+        let matrix_set = MatrixSet::new(&binding);
+        assert_eq!(matrix_set.multiply_all_elements(), Some(0));
+    }
+
+    #[test]
+    fn test_matrix_set_multiply_all_elements_single() {
+        let binding = [Matrix::<f64, 1>::new([2.5])];
+        let matrix_set = MatrixSet::new(&binding);
+        assert_eq!(matrix_set.multiply_all_elements(), Some(2.5));
+    }
+
+    #[test]
+    fn test_matrix_set_multiply_all_elements_empty() {
+        let binding: [Matrix<i32, 3>; 0] = [];
+        assert_eq!(MatrixSet::new(&binding).multiply_all_elements(), None);
+
+        let binding = [Matrix::<i32, 0>::new([]), Matrix::<i32, 0>::new([])];
+        assert_eq!(MatrixSet::new(&binding).multiply_all_elements(), None);
+    }
+}
+#[cfg(test)]
+mod unit_tests_matrix_2d {
+    use crate::Matrix2D;
+
+    #[test]
+    fn test_matrix_2d_vstack() {
+        let top = Matrix2D::<i32, 2, 2>::new([[1, 2], [3, 4]]);
+        let bottom = Matrix2D::<i32, 1, 2>::new([[5, 6]]);
+        assert_eq!(
+            top.vstack(&bottom),
+            vec![vec![1, 2], vec![3, 4], vec![5, 6]]
+        );
+    }
+
+    #[test]
+    fn test_matrix_2d_hstack() {
+        let left = Matrix2D::<i32, 2, 2>::new([[1, 2], [3, 4]]);
+        let right = Matrix2D::<i32, 2, 1>::new([[5], [6]]);
+        assert_eq!(left.hstack(&right), vec![vec![1, 2, 5], vec![3, 4, 6]]);
     }
 }
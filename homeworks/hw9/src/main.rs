@@ -6,5 +6,5 @@ fn main() {
     let matrix_group = [matrix, matrix2];
     let ms = MatrixSet::new(&matrix_group);
     println!("{}", ms.sum_all_elements());
-    println!("{}", ms.multiply_all_elements());
+    println!("{:?}", ms.multiply_all_elements());
 }
@@ -1,7 +1,7 @@
 use log::debug;
 use std::error::Error;
 
-use crate::data_service::DataService;
+use crate::data_service::DataSource;
 use teloxide::{
     payloads::SendMessageSetters,
     prelude::*,
@@ -26,19 +26,22 @@ enum Command {
     ///
     #[command(description = r#"Информация о группах"#)]
     Schedule,
+    /// Groups count
+    #[command(description = r#"Количество групп"#)]
+    Count,
 }
 
 // #[tokio::main]
-pub(crate) async fn run_bot(serv: DataService) -> Result<(), Box<dyn Error>> {
+pub(crate) async fn run_bot<S: DataSource>(serv: S) -> Result<(), Box<dyn Error>> {
     log::info!("Starting buttons bot...");
     let map = dptree::deps![serv.clone()];
 
     let bot = Bot::from_env();
 
     let handler = dptree::entry()
-        .branch(Update::filter_message().endpoint(message_handler))
-        .branch(Update::filter_callback_query().endpoint(callback_handler))
-        .branch(Update::filter_inline_query().endpoint(inline_query_handler));
+        .branch(Update::filter_message().endpoint(message_handler::<S>))
+        .branch(Update::filter_callback_query().endpoint(callback_handler::<S>))
+        .branch(Update::filter_inline_query().endpoint(inline_query_handler::<S>));
 
     Dispatcher::builder(bot, handler)
         .dependencies(map)
@@ -49,7 +52,7 @@ pub(crate) async fn run_bot(serv: DataService) -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
-fn make_keyboard(srv: DataService) -> InlineKeyboardMarkup {
+fn make_keyboard(srv: &impl DataSource) -> InlineKeyboardMarkup {
     let mut keyboard: Vec<Vec<InlineKeyboardButton>> = vec![];
     let groups = srv.data();
 
@@ -66,11 +69,33 @@ fn make_keyboard(srv: DataService) -> InlineKeyboardMarkup {
     InlineKeyboardMarkup::new(keyboard)
 }
 
-async fn message_handler(
+const SCHEDULE_NOT_FOUND: &str = "Расписание не найдено";
+
+fn schedule_message(sched: &[String], max_lines: usize) -> String {
+    if sched.is_empty() {
+        return SCHEDULE_NOT_FOUND.to_string();
+    }
+    sched
+        .iter()
+        .take(max_lines)
+        .map(|s| s.to_string())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn groups_count_message(groups: &[String]) -> String {
+    format!("Доступно групп: {}", groups.len())
+}
+
+fn callback_message(srv: &impl DataSource, selected_course: &str) -> String {
+    schedule_message(&srv.get_sched(selected_course), srv.max_schedule_lines())
+}
+
+async fn message_handler<S: DataSource>(
     bot: Bot,
     msg: Message,
     me: Me,
-    srv: DataService,
+    srv: S,
 ) -> Result<(), Box<dyn Error + Send + Sync>> {
     if let Some(text) = msg.text() {
         match BotCommands::parse(text, me.username()) {
@@ -79,14 +104,19 @@ async fn message_handler(
                     .await?;
             }
             Ok(Command::Schedule) => {
-                let keyboard = make_keyboard(srv);
+                let keyboard = make_keyboard(&srv);
                 bot.send_message(msg.chat.id, "Выберете группу")
                     .reply_markup(keyboard)
                     .await?;
             }
 
+            Ok(Command::Count) => {
+                bot.send_message(msg.chat.id, groups_count_message(&srv.data()))
+                    .await?;
+            }
+
             Ok(Command::Start) => {
-                let keyboard = make_keyboard(srv);
+                let keyboard = make_keyboard(&srv);
                 bot.send_message(msg.chat.id, "Выберете группу")
                     .reply_markup(keyboard)
                     .await?;
@@ -101,17 +131,17 @@ async fn message_handler(
     Ok(())
 }
 
-async fn inline_query_handler(
+async fn inline_query_handler<S: DataSource>(
     bot: Bot,
     q: InlineQuery,
-    srv: DataService,
+    srv: S,
 ) -> Result<(), Box<dyn Error + Send + Sync>> {
     let choice_group = InlineQueryResultArticle::new(
         "0",
         "Выберете группу",
         InputMessageContent::Text(InputMessageContentText::new("Список групп:")),
     )
-    .reply_markup(make_keyboard(srv));
+    .reply_markup(make_keyboard(&srv));
 
     bot.answer_inline_query(q.id, vec![choice_group.into()])
         .await?;
@@ -119,20 +149,14 @@ async fn inline_query_handler(
     Ok(())
 }
 
-async fn callback_handler(
+async fn callback_handler<S: DataSource>(
     bot: Bot,
     q: CallbackQuery,
-    srv: DataService,
+    srv: S,
 ) -> Result<(), Box<dyn Error + Send + Sync>> {
     if let Some(selected_course) = q.data {
         log::info!("You chose: {}", selected_course);
-        let course = srv
-            .get_sched(&selected_course)
-            .iter()
-            .take(5)
-            .map(|s| s.to_string())
-            .collect::<Vec<_>>()
-            .join("\n");
+        let course = callback_message(&srv, &selected_course);
 
         if let Some(Message { id, chat, .. }) = q.message {
             bot.edit_message_text(chat.id, id, course).await?;
@@ -143,3 +167,72 @@ async fn callback_handler(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data_service::InMemoryDataSource;
+    use std::collections::HashMap;
+
+    fn in_memory_source() -> InMemoryDataSource {
+        InMemoryDataSource {
+            schedule: HashMap::from([
+                (
+                    "Rust Developer. Basic".to_string(),
+                    vec!["lesson 1".to_string(), "lesson 2".to_string()],
+                ),
+                ("Rust Developer. Professional".to_string(), vec![]),
+            ]),
+            max_schedule_lines: 1,
+        }
+    }
+
+    #[test]
+    fn test_groups_count_message() {
+        let groups = vec![
+            "Rust Developer. Basic".to_string(),
+            "Rust Developer. Professional".to_string(),
+        ];
+        assert_eq!(groups_count_message(&groups), "Доступно групп: 2");
+        assert_eq!(groups_count_message(&[]), "Доступно групп: 0");
+    }
+
+    #[test]
+    fn test_schedule_message_limit() {
+        let sched = (1..=7).map(|n| format!("lesson {n}")).collect::<Vec<_>>();
+        assert_eq!(schedule_message(&sched, 2), "lesson 1\nlesson 2");
+        assert_eq!(schedule_message(&sched, 5).lines().count(), 5);
+        assert_eq!(schedule_message(&sched, 10).lines().count(), 7);
+    }
+
+    #[test]
+    fn test_schedule_message_empty() {
+        assert_eq!(schedule_message(&[], 5), "Расписание не найдено");
+    }
+
+    #[test]
+    fn test_make_keyboard() {
+        let keyboard = make_keyboard(&in_memory_source());
+        let buttons = keyboard
+            .inline_keyboard
+            .iter()
+            .flatten()
+            .map(|button| button.text.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            buttons,
+            vec!["Rust Developer. Basic", "Rust Developer. Professional"]
+        );
+    }
+
+    #[test]
+    fn test_callback_message() {
+        let srv = in_memory_source();
+        assert_eq!(callback_message(&srv, "Rust Developer. Basic"), "lesson 1");
+        assert_eq!(
+            callback_message(&srv, "Rust Developer. Professional"),
+            "Расписание не найдено"
+        );
+        assert_eq!(callback_message(&srv, "Unknown"), "Расписание не найдено");
+    }
+}
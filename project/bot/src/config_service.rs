@@ -1,26 +1,70 @@
 use config::Config;
 use std::collections::HashMap;
+use std::io;
+use thiserror::Error;
+
+/// The errors of loading the bot configuration.
+#[derive(Debug, Error)]
+pub(crate) enum ConfigError {
+    #[error("config file {0} not found")]
+    NotFound(String),
+    #[error("failed to parse config: {0}")]
+    Parse(#[from] config::ConfigError),
+}
+
 #[derive(Debug)]
 pub(crate) struct AppConfig {
     pub(crate) course_pattern: String,
     pub(crate) source_file: String,
     pub(crate) redis_url: String,
+    pub(crate) max_schedule_lines: usize,
+    pub(crate) redis_ready_attempts: u32,
 }
 
+const CONFIG_PATH_ENV: &str = "PROJECT_CONFIG_PATH";
+const DEFAULT_CONFIG_NAME: &str = "project";
+const DEFAULT_SOURCE_FILE: &str = "/tmp/ttt.html";
 const DEFAULT_PATTERN: &str = "Rust";
 const DEFAULT_REDIS: &str = "redis://127.0.0.1:6379";
+const DEFAULT_MAX_SCHEDULE_LINES: usize = 5;
+const DEFAULT_REDIS_READY_ATTEMPTS: u32 = 3;
+
+pub(crate) fn read_config() -> Result<AppConfig, ConfigError> {
+    let path = config_path(
+        std::env::var(CONFIG_PATH_ENV).ok(),
+        std::env::var("HOME").ok(),
+    );
+    load_config(&path)
+}
 
-pub fn read_config() -> Result<AppConfig, Box<dyn std::error::Error>> {
-    let home_path = format!("{}/.config/project", std::env::var("HOME").unwrap()).to_string();
+/// Returns the config file path: the override if set, otherwise `$HOME/.config/project`,
+/// or `project` in the working directory without `HOME`.
+fn config_path(override_path: Option<String>, home: Option<String>) -> String {
+    match (override_path, home) {
+        (Some(path), _) => path,
+        (None, Some(home)) => format!("{}/.config/{}", home, DEFAULT_CONFIG_NAME),
+        (None, None) => DEFAULT_CONFIG_NAME.to_string(),
+    }
+}
+
+/// Loads the config file, the extension is resolved by the `config` crate.
+fn load_config(path: &str) -> Result<AppConfig, ConfigError> {
     let settings = Config::builder()
-        .add_source(config::File::with_name(&home_path))
+        .add_source(config::File::with_name(path))
         .add_source(config::Environment::with_prefix("OTUS"))
         .build()
-        .unwrap();
+        .map_err(|err| match err {
+            config::ConfigError::Foreign(cause)
+                if cause
+                    .downcast_ref::<io::Error>()
+                    .is_some_and(|cause| cause.kind() == io::ErrorKind::NotFound) =>
+            {
+                ConfigError::NotFound(path.to_string())
+            }
+            err => ConfigError::Parse(err),
+        })?;
 
-    let settings = settings
-        .try_deserialize::<HashMap<String, String>>()
-        .unwrap();
+    let settings = settings.try_deserialize::<HashMap<String, String>>()?;
 
     let course_pattern = settings
         .get("course_pattern")
@@ -34,14 +78,89 @@ pub fn read_config() -> Result<AppConfig, Box<dyn std::error::Error>> {
 
     let source_file = settings
         .get("source_file")
-        .unwrap_or(&"/tmp/ttt.html".to_string())
+        .unwrap_or(&DEFAULT_SOURCE_FILE.to_string())
         .to_string();
 
+    let max_schedule_lines = settings
+        .get("max_schedule_lines")
+        .and_then(|lines| lines.parse().ok())
+        .unwrap_or(DEFAULT_MAX_SCHEDULE_LINES);
+
+    let redis_ready_attempts = settings
+        .get("redis_ready_attempts")
+        .and_then(|attempts| attempts.parse().ok())
+        .unwrap_or(DEFAULT_REDIS_READY_ATTEMPTS);
+
     let config = AppConfig {
         course_pattern,
         redis_url,
         source_file,
+        max_schedule_lines,
+        redis_ready_attempts,
     };
 
     Ok(config)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn temp_config_path(name: &str) -> String {
+        std::env::temp_dir()
+            .join(format!("otus_bot_{}_{}", name, std::process::id()))
+            .to_string_lossy()
+            .to_string()
+    }
+
+    #[test]
+    fn test_config_path() {
+        assert_eq!(
+            config_path(Some("/etc/bot".to_string()), Some("/home/user".to_string())),
+            "/etc/bot"
+        );
+        assert_eq!(
+            config_path(None, Some("/home/user".to_string())),
+            "/home/user/.config/project"
+        );
+        assert_eq!(config_path(None, None), "project");
+    }
+
+    #[test]
+    fn test_read_config_env_override() {
+        let path = temp_config_path("override");
+        fs::write(format!("{}.toml", path), "course_pattern = \"Override\"").unwrap();
+        std::env::set_var(CONFIG_PATH_ENV, &path);
+        let result = read_config();
+        std::env::remove_var(CONFIG_PATH_ENV);
+        fs::remove_file(format!("{}.toml", path)).unwrap();
+        assert_eq!(result.unwrap().course_pattern, "Override");
+    }
+
+    #[test]
+    fn test_load_missing_config() {
+        let path = temp_config_path("missing");
+        assert!(matches!(load_config(&path), Err(ConfigError::NotFound(p)) if p == path));
+    }
+
+    #[test]
+    fn test_load_malformed_config() {
+        let path = temp_config_path("malformed");
+        fs::write(format!("{}.toml", path), "course_pattern = [").unwrap();
+        let result = load_config(&path);
+        fs::remove_file(format!("{}.toml", path)).unwrap();
+        assert!(matches!(result, Err(ConfigError::Parse(_))));
+    }
+
+    #[test]
+    fn test_load_config() {
+        let path = temp_config_path("valid");
+        fs::write(format!("{}.toml", path), "course_pattern = \"Go\"").unwrap();
+        let result = load_config(&path);
+        fs::remove_file(format!("{}.toml", path)).unwrap();
+        let config = result.unwrap();
+        assert_eq!(config.course_pattern, "Go");
+        assert_eq!(config.max_schedule_lines, DEFAULT_MAX_SCHEDULE_LINES);
+    }
+}
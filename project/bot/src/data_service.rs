@@ -1,18 +1,39 @@
-use log::info;
-use redis::Commands;
-use std::collections::{HashMap, HashSet};
-use std::fs;
+use log::{info, warn};
+use rand::Rng;
+use redis::{Commands, RedisResult};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufReader, Read};
+use std::thread;
+use std::time::Duration;
 
 use crate::config_service::AppConfig;
 use scraper::{Html, Selector};
 
 const COURSES_KEY: &str = "COURSES_KEY";
+const SCHEDULE_MARK: char = '\u{1F516}';
+const REDIS_WRITE_ATTEMPTS: u32 = 5;
+const REDIS_RETRY_BASE_DELAY: Duration = Duration::from_millis(100);
+const REDIS_RETRY_MAX_DELAY: Duration = Duration::from_secs(30);
+
+/// The source of the groups and their schedules for the bot handlers.
+pub(crate) trait DataSource: Clone + Send + Sync + 'static {
+    /// Returns the available groups.
+    fn data(&self) -> Vec<String>;
+
+    /// Returns the schedule of the group, empty if the group is unknown.
+    fn get_sched(&self, group: &str) -> Vec<String>;
+
+    /// Returns the maximum number of schedule lines in a reply.
+    fn max_schedule_lines(&self) -> usize;
+}
 
 #[derive(Clone)]
 pub struct DataService {
     client: redis::Client,
     source_file: String,
     course_pattern: String,
+    max_schedule_lines: usize,
 }
 
 impl DataService {
@@ -23,100 +44,298 @@ impl DataService {
             client: redis::Client::open(&*config.redis_url).unwrap(),
             source_file: config.source_file.clone(),
             course_pattern: config.course_pattern.clone(),
+            max_schedule_lines: config.max_schedule_lines,
         }
     }
 
+    /// Checks that Redis answers before the bot starts serving requests.
+    pub(crate) fn check_ready(&self, attempts: u32) -> RedisResult<()> {
+        wait_ready(&self.client, attempts, REDIS_RETRY_BASE_DELAY)
+    }
+
     pub(crate) fn load_sched(self) -> Result<(), Box<dyn std::error::Error>> {
-        self.read_sched().unwrap();
+        self.read_sched()
+    }
+
+    fn read_sched(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let source = BufReader::new(File::open(&self.source_file)?);
+        let schedule = parse_sched(source, &self.course_pattern)?;
+
+        let mut con = self.client.get_connection()?;
+        let courses = serde_json::to_string(&schedule.keys().cloned().collect::<Vec<String>>())?;
+        retry_with_backoff(REDIS_WRITE_ATTEMPTS, REDIS_RETRY_BASE_DELAY, || {
+            con.set::<_, _, ()>(COURSES_KEY, &courses)
+        })?;
+
+        for (course, sched) in &schedule {
+            let sched = serde_json::to_string(sched)?;
+            retry_with_backoff(REDIS_WRITE_ATTEMPTS, REDIS_RETRY_BASE_DELAY, || {
+                con.set::<_, _, ()>(course, &sched)
+            })?;
+        }
+
         Ok(())
     }
+}
 
-    pub(crate) fn data(&self) -> Vec<String> {
+impl DataSource for DataService {
+    fn data(&self) -> Vec<String> {
         let mut con = self.client.get_connection().unwrap();
 
         let gr_str = con.get(COURSES_KEY).unwrap_or_else(|_error| "".to_string());
-        serde_json::from_str::<Vec<String>>(&gr_str)
-            .unwrap()
-            .clone()
+        serde_json::from_str::<Vec<String>>(&gr_str).unwrap_or_default()
     }
 
-    pub(crate) fn get_sched(&self, group: &String) -> Vec<String> {
+    fn get_sched(&self, group: &str) -> Vec<String> {
         let mut con = self.client.get_connection().unwrap();
         let gr_str = con.get(group).unwrap_or_else(|_error| "".to_string());
-        serde_json::from_str::<Vec<String>>(&gr_str)
-            .unwrap()
-            .clone()
+        serde_json::from_str::<Vec<String>>(&gr_str).unwrap_or_default()
     }
 
-    fn read_sched(&self) -> Result<(), Box<dyn std::error::Error>> {
-        const SELECTORS_TABLE: &str = "table.table_full";
-        const SELECTORS_TR: &str = "tr.table__row";
-        const SELECTORS_TD: &str = "td.table__col";
-        let contents = fs::read_to_string(&self.source_file).unwrap();
-
-        let mut courses: HashSet<String> = HashSet::new();
-
-        let mut schedule: HashMap<String, Vec<String>> = HashMap::new();
-
-        let selector = Selector::parse(SELECTORS_TABLE).unwrap();
-        let selector_tr = Selector::parse(SELECTORS_TR).unwrap();
-        let selector_td = Selector::parse(SELECTORS_TD).unwrap();
-        let document = Html::parse_document(&contents);
-
-        document.select(&selector).for_each(|node| {
-            let t: Vec<_> = node.select(&selector_tr).collect();
-
-            t.iter().for_each(|s| {
-                let l = s
-                    .select(&selector_td)
-                    .flat_map(|el| el.text())
-                    .filter(|s| s != &"\n" && !s.is_empty())
-                    .map(|s| s.replace('\n', ""))
-                    .collect::<Vec<_>>();
-                if let Some(s) = l.get(2) {
-                    if s.starts_with(&self.course_pattern) {
-                        courses.insert(s.to_string());
-
-                        let sched = match l.len() == 7 {
-                            true => format!(
-                                "🔖 {} {}: {} ",
-                                &l.first().unwrap(),
-                                &l.get(1).unwrap(),
-                                &l.get(4).unwrap()
-                            ),
-                            _ => format!(
-                                "🔖 {} {}: {} ({})",
-                                &l.first().unwrap(),
-                                &l.get(1).unwrap(),
-                                &l.get(5).unwrap(),
-                                &l.get(6).unwrap()
-                            ),
-                        };
-                        info!("l   {}", &sched);
-                        schedule.entry(s.to_string()).or_default().push(sched);
-                    }
+    fn max_schedule_lines(&self) -> usize {
+        self.max_schedule_lines
+    }
+}
+
+/// The in-memory source of the schedule, used to test the handlers without Redis.
+#[cfg(test)]
+#[derive(Clone, Default)]
+pub(crate) struct InMemoryDataSource {
+    pub(crate) schedule: HashMap<String, Vec<String>>,
+    pub(crate) max_schedule_lines: usize,
+}
+
+#[cfg(test)]
+impl DataSource for InMemoryDataSource {
+    fn data(&self) -> Vec<String> {
+        let mut groups = self.schedule.keys().cloned().collect::<Vec<_>>();
+        groups.sort();
+        groups
+    }
+
+    fn get_sched(&self, group: &str) -> Vec<String> {
+        self.schedule.get(group).cloned().unwrap_or_default()
+    }
+
+    fn max_schedule_lines(&self) -> usize {
+        self.max_schedule_lines
+    }
+}
+
+/// A storage that can be checked for availability.
+pub(crate) trait Ping {
+    fn ping(&self) -> RedisResult<()>;
+}
+
+impl Ping for redis::Client {
+    fn ping(&self) -> RedisResult<()> {
+        let mut con = self.get_connection()?;
+        redis::cmd("PING").query(&mut con)
+    }
+}
+
+/// Waits until the storage answers the ping, retrying with a backoff.
+fn wait_ready(storage: &impl Ping, attempts: u32, base_delay: Duration) -> RedisResult<()> {
+    retry_with_backoff(attempts, base_delay, || storage.ping())
+}
+
+/// Runs the Redis command, retrying failures with a jittered exponential backoff.
+///
+/// Returns the last error once all the attempts are exhausted.
+fn retry_with_backoff<T>(
+    attempts: u32,
+    base_delay: Duration,
+    mut command: impl FnMut() -> RedisResult<T>,
+) -> RedisResult<T> {
+    let mut attempt = 1;
+    loop {
+        match command() {
+            Ok(res) => return Ok(res),
+            Err(err) if attempt >= attempts => return Err(err),
+            Err(err) => {
+                let delay = backoff_delay(base_delay, attempt);
+                let jitter = rand::thread_rng().gen_range(0..=delay.as_millis() as u64);
+                let delay = delay + Duration::from_millis(jitter);
+                warn!("redis write failed (attempt {attempt}): {err}, retry in {delay:?}");
+                thread::sleep(delay);
+                attempt += 1;
+            }
+        }
+    }
+}
+
+/// Returns the delay before the next attempt, doubled per attempt up to `REDIS_RETRY_MAX_DELAY`.
+fn backoff_delay(base_delay: Duration, attempt: u32) -> Duration {
+    base_delay
+        .saturating_mul(2u32.saturating_pow(attempt - 1))
+        .min(REDIS_RETRY_MAX_DELAY)
+}
+
+/// Parses the schedule page and groups the lessons by the courses matching the pattern.
+pub(crate) fn parse_sched(
+    mut source: impl Read,
+    course_pattern: &str,
+) -> io::Result<HashMap<String, Vec<String>>> {
+    const SELECTORS_TABLE: &str = "table.table_full";
+    const SELECTORS_TR: &str = "tr.table__row";
+    const SELECTORS_TD: &str = "td.table__col";
+    let mut contents = String::new();
+    source.read_to_string(&mut contents)?;
+
+    let mut schedule: HashMap<String, Vec<String>> = HashMap::new();
+
+    let selector = Selector::parse(SELECTORS_TABLE).unwrap();
+    let selector_tr = Selector::parse(SELECTORS_TR).unwrap();
+    let selector_td = Selector::parse(SELECTORS_TD).unwrap();
+    let document = Html::parse_document(&contents);
+
+    document.select(&selector).for_each(|node| {
+        let t: Vec<_> = node.select(&selector_tr).collect();
+
+        t.iter().for_each(|s| {
+            let l = s
+                .select(&selector_td)
+                .flat_map(|el| el.text())
+                .filter(|s| s != &"\n" && !s.is_empty())
+                .map(|s| s.replace('\n', ""))
+                .collect::<Vec<_>>();
+            if let Some(s) = l.get(2) {
+                if s.starts_with(course_pattern) {
+                    let sched = format_sched_line(&l);
+                    info!("l   {}", &sched);
+                    schedule.entry(s.to_string()).or_default().push(sched);
                 }
-            });
+            }
         });
-        let mut con = self.client.get_connection().unwrap();
-        let _r: Result<String, redis::RedisError> = con.set(
-            COURSES_KEY,
-            serde_json::to_string(
-                &courses
-                    .iter()
-                    .map(|s| s.to_string())
-                    .collect::<Vec<String>>(),
-            )
-            .unwrap(),
+    });
+
+    Ok(schedule)
+}
+
+/// Builds the schedule line from the cells of the table row.
+fn format_sched_line(cells: &[String]) -> String {
+    match cells.len() == 7 {
+        true => format!(
+            "{SCHEDULE_MARK} {} {}: {} ",
+            &cells[0], &cells[1], &cells[4]
+        ),
+        _ => format!(
+            "{SCHEDULE_MARK} {} {}: {} ({})",
+            &cells[0], &cells[1], &cells[5], &cells[6]
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use redis::{ErrorKind, RedisError};
+    use std::cell::Cell;
+
+    struct FakeStorage {
+        failures: Cell<u32>,
+    }
+
+    impl Ping for FakeStorage {
+        fn ping(&self) -> RedisResult<()> {
+            if self.failures.get() == 0 {
+                return Ok(());
+            }
+            self.failures.set(self.failures.get() - 1);
+            Err(RedisError::from((ErrorKind::IoError, "connection refused")))
+        }
+    }
+
+    const FIXTURE: &str = r#"
+<table class="table_full">
+  <tr class="table__row">
+    <td class="table__col">01.02</td><td class="table__col">20:00</td>
+    <td class="table__col">Rust Developer. Basic</td><td class="table__col">Online</td>
+    <td class="table__col">Traits</td><td class="table__col">2h</td><td class="table__col">-</td>
+  </tr>
+  <tr class="table__row">
+    <td class="table__col">03.02</td><td class="table__col">20:00</td>
+    <td class="table__col">Rust Developer. Basic</td><td class="table__col">Online</td>
+    <td class="table__col">Lesson</td><td class="table__col">Macros</td>
+    <td class="table__col">Webinar</td><td class="table__col">2h</td>
+  </tr>
+  <tr class="table__row">
+    <td class="table__col">02.02</td><td class="table__col">19:00</td>
+    <td class="table__col">Golang Developer</td><td class="table__col">Online</td>
+    <td class="table__col">Goroutines</td><td class="table__col">2h</td><td class="table__col">-</td>
+  </tr>
+</table>"#;
+
+    #[test]
+    fn test_parse_sched() {
+        let schedule = parse_sched(FIXTURE.as_bytes(), "Rust").unwrap();
+        assert_eq!(schedule.len(), 1);
+        assert_eq!(
+            schedule.get("Rust Developer. Basic").unwrap(),
+            &vec![
+                "\u{1F516} 01.02 20:00: Traits ".to_string(),
+                "\u{1F516} 03.02 20:00: Macros (Webinar)".to_string()
+            ]
         );
+    }
+
+    #[test]
+    fn test_format_sched_line() {
+        let cells = ["01.02", "20:00", "Rust", "Online", "Traits", "2h", "-"].map(String::from);
+        let line = format_sched_line(&cells);
+        assert_eq!(line.chars().next(), Some('\u{1F516}'));
+        assert_eq!(line, "\u{1F516} 01.02 20:00: Traits ");
+    }
 
-        courses.iter().for_each(|s| {
-            if schedule.contains_key(s) {
-                let _: Result<String, redis::RedisError> =
-                    con.set(s, serde_json::to_string(&schedule.get(s)).unwrap());
+    #[test]
+    fn test_wait_ready() {
+        let reachable = FakeStorage {
+            failures: Cell::new(0),
+        };
+        assert!(wait_ready(&reachable, 3, Duration::ZERO).is_ok());
+
+        let starting = FakeStorage {
+            failures: Cell::new(2),
+        };
+        assert!(wait_ready(&starting, 3, Duration::ZERO).is_ok());
+
+        let unreachable = FakeStorage {
+            failures: Cell::new(u32::MAX),
+        };
+        assert!(wait_ready(&unreachable, 3, Duration::ZERO).is_err());
+    }
+
+    #[test]
+    fn test_retry_with_backoff() {
+        let mut attempts = 0;
+        let res = retry_with_backoff(3, Duration::ZERO, || {
+            attempts += 1;
+            if attempts < 3 {
+                Err(RedisError::from((ErrorKind::IoError, "connection refused")))
+            } else {
+                Ok("OK")
             }
         });
+        assert_eq!(res.unwrap(), "OK");
+        assert_eq!(attempts, 3);
+    }
 
-        Ok(())
+    #[test]
+    fn test_backoff_delay() {
+        let base_delay = Duration::from_millis(100);
+        assert_eq!(backoff_delay(base_delay, 1), base_delay);
+        assert_eq!(backoff_delay(base_delay, 3), Duration::from_millis(400));
+        assert_eq!(backoff_delay(base_delay, 64), REDIS_RETRY_MAX_DELAY);
+    }
+
+    #[test]
+    fn test_retry_with_backoff_exhausted() {
+        let mut attempts = 0;
+        let res: RedisResult<()> = retry_with_backoff(2, Duration::ZERO, || {
+            attempts += 1;
+            Err(RedisError::from((ErrorKind::IoError, "connection refused")))
+        });
+        assert!(res.is_err());
+        assert_eq!(attempts, 2);
     }
 }
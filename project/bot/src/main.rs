@@ -18,6 +18,10 @@ async fn main() -> Result<(), Box<dyn Error>> {
 
     let config = read_config()?;
     let serv = DataService::new(&config);
+    if let Err(err) = serv.check_ready(config.redis_ready_attempts) {
+        log::error!("redis is not available at {}: {}", config.redis_url, err);
+        return Err(err.into());
+    }
     let data_serv = serv.clone();
 
     debug!(
@@ -25,8 +29,11 @@ async fn main() -> Result<(), Box<dyn Error>> {
         config.source_file
     );
 
-    task::spawn(async move {
-        let _ = serv.clone().load_sched();
+    // the Redis client and the retries block, so keep them off the async workers
+    task::spawn_blocking(move || {
+        if let Err(err) = serv.load_sched() {
+            log::error!("failed to load schedule: {}", err);
+        }
     });
 
     run_bot(data_serv).await